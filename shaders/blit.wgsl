@@ -0,0 +1,19 @@
+#include "post.wgsl"
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Вывод на экран. В sRGB-вид поверхности пишется линейный цвет, кодирует его сам формат;
+// с ENCODE_SRGB (формат без sRGB-варианта) кодируем здесь
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+#ifdef ENCODE_SRGB
+    return vec4<f32>(linear_to_srgb(color), 1.0);
+#else
+    return vec4<f32>(color, 1.0);
+#endif
+}
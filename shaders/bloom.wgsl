@@ -0,0 +1,32 @@
+#include "post.wgsl"
+
+// Часть кадра ярче порога, в половинном разрешении (билинейная выборка усредняет 2×2).
+// Порог сравнивается с яркостью (Rec. 709) сцены до экспозиции, иначе с ростом экспозиции светилась бы
+// любая освещённая грань. Цвет масштабируется на долю излишка и сохраняет оттенок, а сам излишек
+// растёт с экспозицией вместе с кадром.
+@fragment
+fn fs_threshold(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let excess = max(luminance - post.bloom_threshold, 0.0);
+    return vec4<f32>(color * (excess / max(luminance, 1e-4)) * post.exposure, 1.0);
+}
+
+// Гаусс на 9 текселей за 5 выборок: соседние веса сложены в одну линейную выборку
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let step = direction / vec2<f32>(textureDimensions(source));
+    var color = sample_source(uv) * 0.2270270270;
+    color += (sample_source(uv + step * 1.3846153846) + sample_source(uv - step * 1.3846153846)) * 0.3162162162;
+    color += (sample_source(uv + step * 3.2307692308) + sample_source(uv - step * 3.2307692308)) * 0.0702702703;
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_vertical(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
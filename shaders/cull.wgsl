@@ -0,0 +1,77 @@
+struct Cull {
+    planes: array<vec4<f32>, 6>,
+    camera_position: vec3<f32>,
+    draw_count: u32,
+    lod_distances: vec4<f32>,
+};
+
+struct ChunkInfo {
+    aabb_min: vec3<f32>,
+    first_index: u32,
+    aabb_max: vec3<f32>,
+    index_count: u32,
+    base_vertex: i32,
+    lod: u32,
+    _pad0: u32,
+    _pad1: u32,
+};
+
+struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> cull: Cull;
+@group(0) @binding(1)
+var<storage, read> chunks: array<ChunkInfo>;
+@group(0) @binding(2)
+var<storage, read_write> draws: array<DrawIndexedIndirectArgs>;
+
+// AABB отбрасывается, только если целиком за одной из плоскостей
+fn is_visible(aabb_min: vec3<f32>, aabb_max: vec3<f32>) -> bool {
+    for (var i = 0u; i < 6u; i++) {
+        let plane = cull.planes[i];
+        let positive = select(aabb_min, aabb_max, plane.xyz >= vec3<f32>(0.0));
+        if dot(plane.xyz, positive) + plane.w < 0.0 {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Уровень детализации по расстоянию от камеры до центра чанка
+fn select_lod(aabb_min: vec3<f32>, aabb_max: vec3<f32>) -> u32 {
+    let distance = length((aabb_min + aabb_max) * 0.5 - cull.camera_position);
+    var lod = 0u;
+    for (var i = 0u; i < 3u; i++) {
+        if distance >= cull.lod_distances[i] {
+            lod += 1u;
+        }
+    }
+    return lod;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= cull.draw_count {
+        return;
+    }
+
+    let chunk = chunks[index];
+    var args: DrawIndexedIndirectArgs;
+    args.index_count = chunk.index_count;
+    args.first_index = chunk.first_index;
+    args.base_vertex = chunk.base_vertex;
+    args.first_instance = 0u;
+    args.instance_count = 0u;
+    let selected = chunk.lod == select_lod(chunk.aabb_min, chunk.aabb_max);
+    if chunk.index_count > 0u && selected && is_visible(chunk.aabb_min, chunk.aabb_max) {
+        args.instance_count = 1u;
+    }
+    draws[index] = args;
+}
@@ -0,0 +1,38 @@
+#include "post.wgsl"
+
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const SPAN_MAX: f32 = 8.0;
+
+// Яркость по гамма-сжатому цвету: источник линейный, а края видны в воспринимаемой яркости
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(sqrt(color), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+// FXAA: размытие вдоль направления края, найденного по яркости соседей по диагонали
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    let luma_nw = luma(sample_source(in.uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample_source(in.uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(sample_source(in.uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(sample_source(in.uv + vec2<f32>(1.0, 1.0) * texel));
+    let center = sample_source(in.uv);
+    let luma_m = luma(center);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(-((luma_nw + luma_ne) - (luma_sw + luma_se)), (luma_nw + luma_sw) - (luma_ne + luma_se));
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let inner = 0.5 * (sample_source(in.uv + direction * (1.0 / 3.0 - 0.5)) + sample_source(in.uv + direction * (2.0 / 3.0 - 0.5)));
+    let outer = inner * 0.5 + 0.25 * (sample_source(in.uv - direction * 0.5) + sample_source(in.uv + direction * 0.5));
+    let luma_outer = luma(outer);
+    // Широкая выборка вышла за пределы соседей – задела другой край, берём узкую
+    if luma_outer < luma_min || luma_outer > luma_max {
+        return vec4<f32>(inner, 1.0);
+    }
+    return vec4<f32>(outer, 1.0);
+}
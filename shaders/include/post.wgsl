@@ -0,0 +1,34 @@
+// Общее для проходов постобработки: полноэкранный треугольник, исходная текстура и параметры.
+// Повторяет PostUniform из post.rs.
+struct Post {
+    exposure: f32,
+    tonemap: u32,
+    bloom_threshold: f32,
+    bloom_strength: f32,
+};
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post: Post;
+
+struct FullscreenOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Треугольник из vertex_index, вершинных буферов нет; uv (0, 0) – левый верхний угол
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> FullscreenOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: FullscreenOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(source, source_sampler, uv, 0.0).rgb;
+}
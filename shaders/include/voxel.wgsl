@@ -0,0 +1,12 @@
+// Общие функции для шейдеров, читающих сетку вокселей.
+// Ожидают в модуле переменную `grid: Grid`. unpack_color генерируется из Rust в <types>.
+#include <types>
+
+fn cell_index(cell: vec3<i32>) -> u32 {
+    let size = i32(grid.size);
+    return u32(cell.x + cell.y * size + cell.z * size * size);
+}
+
+fn in_grid(cell: vec3<i32>) -> bool {
+    return all(cell >= vec3<i32>(0)) && all(cell < vec3<i32>(i32(grid.size)));
+}
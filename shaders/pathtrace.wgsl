@@ -0,0 +1,192 @@
+struct PathTrace {
+    inv_view_proj: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    sun_direction: vec4<f32>,
+    size: vec2<u32>,
+    frame: u32,             // Номер кадра накопления (для генератора случайных чисел)
+    samples_per_frame: u32,
+};
+
+#include "voxel.wgsl"
+
+@group(0) @binding(0)
+var<uniform> params: PathTrace;
+@group(0) @binding(1)
+var<storage, read> voxels: array<Voxel>;
+@group(0) @binding(2)
+var<storage, read> light_levels: array<u32>;
+@group(0) @binding(3)
+var<uniform> grid: Grid;
+// rgb – сумма сэмплов, w – их количество
+@group(0) @binding(4)
+var<storage, read_write> accumulation: array<vec4<f32>>;
+
+const PI: f32 = 3.14159265;
+const SUN_COLOR: vec3<f32> = vec3<f32>(1.0, 0.95, 0.85);
+const SKY_COLOR: vec3<f32> = vec3<f32>(0.45, 0.6, 0.9);
+
+// PCG-хеш как генератор случайных чисел
+var<private> rng_state: u32;
+
+fn random() -> f32 {
+    rng_state = rng_state * 747796405u + 2891336453u;
+    var word = ((rng_state >> ((rng_state >> 28u) + 4u)) ^ rng_state) * 277803737u;
+    word = (word >> 22u) ^ word;
+    return f32(word) / 4294967295.0;
+}
+
+struct Hit {
+    hit: bool,
+    cell: vec3<i32>,
+    normal: vec3<f32>,
+    t: f32, // Расстояние в единицах сетки
+};
+
+// DDA по сетке; origin и direction – в координатах сетки (один воксель = 1)
+fn trace(origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var result: Hit;
+    result.hit = false;
+
+    let size = f32(grid.size);
+    let inv = 1.0 / direction;
+    let t0 = (vec3<f32>(0.0) - origin) * inv;
+    let t1 = (vec3<f32>(size) - origin) * inv;
+    let t_min = min(t0, t1);
+    let t_max_box = max(t0, t1);
+    let t_near = max(max(t_min.x, t_min.y), t_min.z);
+    let t_far = min(min(t_max_box.x, t_max_box.y), t_max_box.z);
+    if t_near > t_far || t_far < 0.0 {
+        return result;
+    }
+
+    let t_start = max(t_near, 0.0);
+    let start = origin + direction * (t_start + 1e-4);
+    var cell = vec3<i32>(floor(start));
+    let step = vec3<i32>(sign(direction));
+    let t_delta = abs(inv);
+    var t_max = (select(vec3<f32>(cell), vec3<f32>(cell + vec3<i32>(1)), direction > vec3<f32>(0.0)) - start) * inv + t_start;
+    var normal = -vec3<f32>(step) * select(vec3<f32>(0.0), vec3<f32>(1.0), t_min == vec3<f32>(t_near));
+    var t = t_start;
+
+    let max_steps = i32(grid.size) * 3;
+    for (var i = 0; i < max_steps; i++) {
+        if !in_grid(cell) {
+            break;
+        }
+        if voxels[cell_index(cell)].voxel_type != VOXEL_EMPTY {
+            result.hit = true;
+            result.cell = cell;
+            result.normal = normal;
+            result.t = t;
+            return result;
+        }
+
+        if t_max.x < t_max.y && t_max.x < t_max.z {
+            t = t_max.x;
+            t_max.x += t_delta.x;
+            cell.x += step.x;
+            normal = vec3<f32>(-f32(step.x), 0.0, 0.0);
+        } else if t_max.y < t_max.z {
+            t = t_max.y;
+            t_max.y += t_delta.y;
+            cell.y += step.y;
+            normal = vec3<f32>(0.0, -f32(step.y), 0.0);
+        } else {
+            t = t_max.z;
+            t_max.z += t_delta.z;
+            cell.z += step.z;
+            normal = vec3<f32>(0.0, 0.0, -f32(step.z));
+        }
+    }
+    return result;
+}
+
+// Цветной свет блоков в ячейке
+fn block_light(cell: vec3<i32>) -> vec3<f32> {
+    if !in_grid(cell) {
+        return vec3<f32>(0.0);
+    }
+    let packed = light_levels[cell_index(cell)];
+    return vec3<f32>(f32((packed >> 4u) & 0xFu), f32((packed >> 8u) & 0xFu), f32((packed >> 12u) & 0xFu)) / 15.0;
+}
+
+// Направление по косинусному распределению вокруг нормали
+fn cosine_sample(normal: vec3<f32>) -> vec3<f32> {
+    let r1 = random();
+    let r2 = random();
+    let phi = 2.0 * PI * r1;
+    let r = sqrt(r2);
+    let tangent = normalize(select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), abs(normal.x) > 0.5));
+    let u = normalize(cross(tangent, normal));
+    let v = cross(normal, u);
+    return normalize(u * (r * cos(phi)) + v * (r * sin(phi)) + normal * sqrt(1.0 - r2));
+}
+
+// Прямое освещение точки на грани: солнце (с теневым лучом), излучение и свет блоков
+fn direct_light(position: vec3<f32>, hit: Hit) -> vec3<f32> {
+    let to_sun = -normalize(params.sun_direction.xyz);
+    var light = block_light(hit.cell + vec3<i32>(hit.normal));
+    let n_dot_l = dot(hit.normal, to_sun);
+    if n_dot_l > 0.0 && !trace(position + hit.normal * 1e-3, to_sun).hit {
+        light += SUN_COLOR * n_dot_l;
+    }
+    return light;
+}
+
+fn emission(voxel: Voxel) -> vec3<f32> {
+    if voxel.voxel_type == VOXEL_EMISSIVE {
+        return unpack_color(voxel.color).rgb;
+    }
+    return vec3<f32>(0.0);
+}
+
+fn radiance(origin: vec3<f32>, direction: vec3<f32>) -> vec3<f32> {
+    let primary = trace(origin, direction);
+    if !primary.hit {
+        return SKY_COLOR;
+    }
+    let voxel = voxels[cell_index(primary.cell)];
+    let albedo = unpack_color(voxel.color).rgb;
+    let position = origin + direction * primary.t;
+    var color = emission(voxel) + albedo * direct_light(position, primary);
+
+    // Один отскок рассеянного света; при косинусной выборке cos/pdf сокращаются
+    let bounce_dir = cosine_sample(primary.normal);
+    let bounce_origin = position + primary.normal * 1e-3;
+    let bounce = trace(bounce_origin, bounce_dir);
+    if bounce.hit {
+        let bounce_voxel = voxels[cell_index(bounce.cell)];
+        let bounce_albedo = unpack_color(bounce_voxel.color).rgb;
+        let bounce_position = bounce_origin + bounce_dir * bounce.t;
+        color += albedo * (emission(bounce_voxel) + bounce_albedo * direct_light(bounce_position, bounce));
+    } else {
+        color += albedo * SKY_COLOR;
+    }
+    return color;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.size.x || id.y >= params.size.y {
+        return;
+    }
+    let pixel = id.y * params.size.x + id.x;
+    rng_state = pixel * 1973u + params.frame * 9277u + 26699u;
+
+    var sum = vec3<f32>(0.0);
+    for (var s = 0u; s < params.samples_per_frame; s++) {
+        // Случайное смещение внутри пикселя – бесплатное сглаживание
+        let jitter = vec2<f32>(random(), random());
+        let uv = (vec2<f32>(id.xy) + jitter) / vec2<f32>(params.size);
+        let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+        // От ближней плоскости к дальней: верно и для ортографии
+        let near_point = params.inv_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+        let far_point = params.inv_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+        let world_near = near_point.xyz / near_point.w;
+        let world_dir = normalize(far_point.xyz / far_point.w - world_near);
+        let origin = (world_near - grid.origin) / grid.voxel_size;
+        sum += radiance(origin, world_dir);
+    }
+
+    accumulation[pixel] += vec4<f32>(sum, f32(params.samples_per_frame));
+}
@@ -0,0 +1,30 @@
+struct PathTrace {
+    inv_view_proj: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    sun_direction: vec4<f32>,
+    size: vec2<u32>,
+    frame: u32,
+    samples_per_frame: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: PathTrace;
+@group(0) @binding(1)
+var<storage, read> accumulation: array<vec4<f32>>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Показывает среднее накопленных сэмплов (HDR, тонмаппинг дальше по цепочке)
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = vec2<u32>(position.xy);
+    let value = accumulation[pixel.y * params.size.x + pixel.x];
+    if value.w == 0.0 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    return vec4<f32>(value.rgb / value.w, 1.0);
+}
@@ -0,0 +1,160 @@
+struct RayMarch {
+    inv_view_proj: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    sun_direction: vec4<f32>,
+    viewport: vec4<f32>, // xy – размер экрана в пикселях
+};
+
+#include "voxel.wgsl"
+
+@group(0) @binding(0)
+var<uniform> params: RayMarch;
+
+@group(1) @binding(0)
+var<storage, read> voxels: array<Voxel>;
+@group(1) @binding(1)
+var<storage, read> light_levels: array<u32>;
+@group(1) @binding(2)
+var<uniform> grid: Grid;
+
+struct Hit {
+    hit: bool,
+    cell: vec3<i32>,
+    normal: vec3<f32>,
+    t: f32, // Расстояние в единицах сетки
+};
+
+// DDA по сетке; origin и direction – в координатах сетки (один воксель = 1)
+fn trace(origin: vec3<f32>, direction: vec3<f32>, max_t: f32) -> Hit {
+    var result: Hit;
+    result.hit = false;
+
+    // Пересечение луча с кубом сетки
+    let size = f32(grid.size);
+    let inv = 1.0 / direction;
+    let t0 = (vec3<f32>(0.0) - origin) * inv;
+    let t1 = (vec3<f32>(size) - origin) * inv;
+    let t_min = min(t0, t1);
+    let t_max_box = max(t0, t1);
+    let t_near = max(max(t_min.x, t_min.y), t_min.z);
+    let t_far = min(min(t_max_box.x, t_max_box.y), t_max_box.z);
+    if t_near > t_far || t_far < 0.0 {
+        return result;
+    }
+
+    let t_start = max(t_near, 0.0);
+    let start = origin + direction * (t_start + 1e-4);
+    var cell = vec3<i32>(floor(start));
+    let step = vec3<i32>(sign(direction));
+    let t_delta = abs(inv);
+    var t_max = (select(vec3<f32>(cell), vec3<f32>(cell + vec3<i32>(1)), direction > vec3<f32>(0.0)) - start) * inv + t_start;
+    // Нормаль грани куба сетки, через которую вошёл луч
+    var normal = -vec3<f32>(step) * select(vec3<f32>(0.0), vec3<f32>(1.0), t_min == vec3<f32>(t_near));
+    var t = t_start;
+
+    let max_steps = i32(grid.size) * 3;
+    for (var i = 0; i < max_steps; i++) {
+        if !in_grid(cell) || t > max_t {
+            break;
+        }
+        if voxels[cell_index(cell)].voxel_type != VOXEL_EMPTY {
+            result.hit = true;
+            result.cell = cell;
+            result.normal = normal;
+            result.t = t;
+            return result;
+        }
+
+        if t_max.x < t_max.y && t_max.x < t_max.z {
+            t = t_max.x;
+            t_max.x += t_delta.x;
+            cell.x += step.x;
+            normal = vec3<f32>(-f32(step.x), 0.0, 0.0);
+        } else if t_max.y < t_max.z {
+            t = t_max.y;
+            t_max.y += t_delta.y;
+            cell.y += step.y;
+            normal = vec3<f32>(0.0, -f32(step.y), 0.0);
+        } else {
+            t = t_max.z;
+            t_max.z += t_delta.z;
+            cell.z += step.z;
+            normal = vec3<f32>(0.0, 0.0, -f32(step.z));
+        }
+    }
+    return result;
+}
+
+// Уровни освещения ячейки: x – небо, yzw – цветной свет блоков
+fn sample_light(cell: vec3<i32>) -> vec4<f32> {
+    if !in_grid(cell) {
+        return vec4<f32>(1.0, 0.0, 0.0, 0.0);
+    }
+    let packed = light_levels[cell_index(cell)];
+    let levels = vec4<u32>(packed & 0xFu, (packed >> 4u) & 0xFu, (packed >> 8u) & 0xFu, (packed >> 12u) & 0xFu);
+    return vec4<f32>(levels) / 15.0;
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+// Полноэкранный треугольник без вершинного буфера
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+};
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    // Восстанавливаем луч из пикселя через обратную view-projection:
+    // от ближней плоскости к дальней, так он верен и для ортографии
+    let ndc = vec2<f32>(in.position.x / params.viewport.x * 2.0 - 1.0, 1.0 - in.position.y / params.viewport.y * 2.0);
+    let near_point = params.inv_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+    let far_point = params.inv_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+    let world_far = far_point.xyz / far_point.w;
+    let world_origin = near_point.xyz / near_point.w;
+    let world_dir = normalize(world_far - world_origin);
+
+    let origin = (world_origin - grid.origin) / grid.voxel_size;
+    let hit = trace(origin, world_dir, length(world_far - world_origin) / grid.voxel_size);
+
+    var out: FragmentOutput;
+    if !hit.hit {
+        out.color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        out.depth = 1.0;
+        return out;
+    }
+
+    let world_hit = world_origin + world_dir * hit.t * grid.voxel_size;
+    let clip = params.view_proj * vec4<f32>(world_hit, 1.0);
+    out.depth = clip.z / clip.w;
+
+    // Теневой луч к солнцу от центра грани
+    let to_sun = -normalize(params.sun_direction.xyz);
+    let diffuse = max(dot(hit.normal, to_sun), 0.0);
+    var visibility = 0.0;
+    if diffuse > 0.0 {
+        let shadow_origin = (world_hit - grid.origin) / grid.voxel_size + hit.normal * 1e-3;
+        visibility = select(1.0, 0.0, trace(shadow_origin, to_sun, f32(grid.size) * 2.0).hit);
+    }
+
+    let levels = sample_light(hit.cell + vec3<i32>(hit.normal));
+    let sky = levels.x;
+    let light = vec3<f32>(0.05 + 0.3 * sky + 0.65 * diffuse * visibility * sky) + levels.yzw;
+
+    let voxel = voxels[cell_index(hit.cell)];
+    let color = unpack_color(voxel.color);
+    let emitted = select(vec3<f32>(0.0), color.rgb * EMISSIVE_STRENGTH, voxel.voxel_type == VOXEL_EMISSIVE);
+    out.color = vec4<f32>(color.rgb * light + emitted, color.a);
+    return out;
+}
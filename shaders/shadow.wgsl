@@ -0,0 +1,7 @@
+@group(0) @binding(0)
+var<uniform> light_view_proj: mat4x4<f32>;
+
+@vertex
+fn main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return light_view_proj * vec4<f32>(position, 1.0);
+}
@@ -0,0 +1,48 @@
+#include "post.wgsl"
+
+@group(0) @binding(3)
+var bloom: texture_2d<f32>;
+
+// Значения Tonemap из post.rs
+const TONEMAP_ACES: u32 = 1u;
+const TONEMAP_FILMIC: u32 = 2u;
+
+// Аппроксимация ACES (Narkowicz)
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Кривая Hable (Uncharted 2)
+fn hable(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f;
+}
+
+fn filmic(x: vec3<f32>) -> vec3<f32> {
+    let white = 11.2;
+    return clamp(hable(x * 2.0) / hable(vec3<f32>(white)), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Экспозиция, bloom и сжатие HDR в [0, 1]; результат линейный
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    var color = sample_source(in.uv) * post.exposure;
+    color += textureSampleLevel(bloom, source_sampler, in.uv, 0.0).rgb * post.bloom_strength;
+    switch post.tonemap {
+        case TONEMAP_ACES: {
+            color = aces(color);
+        }
+        case TONEMAP_FILMIC: {
+            color = filmic(color);
+        }
+        default: {
+            color = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -1,4 +1,100 @@
+struct Shadow {
+    light_view_proj: array<mat4x4<f32>, CASCADE_COUNT>,
+    splits: vec4<f32>,
+    sun_direction: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> shadow: Shadow;
+@group(2) @binding(1)
+var shadow_map: texture_depth_2d_array;
+@group(2) @binding(2)
+var shadow_sampler: sampler_comparison;
+
+#include <types>
+
+@group(1) @binding(1)
+var<storage, read> light_levels: array<u32>;
+@group(1) @binding(2)
+var<uniform> grid: Grid;
+
+struct FragmentInput {
+    @location(0) color: vec4<f32>,
+    @location(1) ao: f32,
+    @location(2) world_position: vec3<f32>,
+    @location(3) normal: vec3<f32>,
+    @location(4) view_depth: f32,
+    @location(5) emission: f32,
+};
+
+fn select_cascade(view_depth: f32) -> u32 {
+    for (var i = 0u; i < CASCADE_COUNT; i++) {
+        if view_depth < shadow.splits[i] {
+            return i;
+        }
+    }
+    return CASCADE_COUNT - 1u;
+}
+
+// С SHADOW_PCF – PCF 3×3 поверх аппаратного сравнения 2×2, без него – одна выборка
+fn sample_shadow(world_position: vec3<f32>, view_depth: f32) -> f32 {
+    // Дальше последнего каскада теней нет
+    if view_depth >= shadow.splits[CASCADE_COUNT - 1u] {
+        return 1.0;
+    }
+    let cascade = select_cascade(view_depth);
+    let light_pos = shadow.light_view_proj[cascade] * vec4<f32>(world_position, 1.0);
+    let ndc = light_pos.xyz / light_pos.w;
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+#ifdef SHADOW_PCF
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, cascade, ndc.z);
+        }
+    }
+    return lit / 9.0;
+#else
+    return textureSampleCompareLevel(shadow_map, shadow_sampler, uv, cascade, ndc.z);
+#endif
+}
+
+// Уровни освещения ячейки перед гранью: x – небо, yzw – цветной свет блоков
+fn sample_light(world_position: vec3<f32>, normal: vec3<f32>) -> vec4<f32> {
+    let cell = vec3<i32>(floor((world_position - grid.origin) / grid.voxel_size + normal * 0.5));
+    let size = i32(grid.size);
+    if any(cell < vec3<i32>(0)) || any(cell >= vec3<i32>(size)) {
+        return vec4<f32>(1.0, 0.0, 0.0, 0.0);
+    }
+    let packed = light_levels[u32(cell.x + cell.y * size + cell.z * size * size)];
+    let levels = vec4<u32>(packed & 0xFu, (packed >> 4u) & 0xFu, (packed >> 8u) & 0xFu, (packed >> 12u) & 0xFu);
+    return vec4<f32>(levels) / 15.0;
+}
+
 @fragment
-fn main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
-    return color;
+fn main(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Мягкая кривая, чтобы полностью закрытые углы не уходили в чёрный
+    let occlusion = mix(0.35, 1.0, in.ao);
+
+    let to_sun = -normalize(shadow.sun_direction.xyz);
+    let diffuse = max(dot(normalize(in.normal), to_sun), 0.0);
+    var visibility = 0.0;
+    if diffuse > 0.0 {
+        visibility = sample_shadow(in.world_position, in.view_depth);
+    }
+    let levels = sample_light(in.world_position, in.normal);
+    let sky = levels.x;
+    // Солнце и рассеянный свет неба гасятся в пещерах, свет блоков добавляется сверху
+    let light = vec3<f32>(0.05 + 0.3 * sky + 0.65 * diffuse * visibility * sky) + levels.yzw;
+
+    // Свечение не затеняется и в HDR ярче белого
+    let emitted = in.color.rgb * in.emission * EMISSIVE_STRENGTH;
+    return vec4<f32>(in.color.rgb * occlusion * light + emitted, in.color.a);
 }
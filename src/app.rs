@@ -1,38 +1,70 @@
-use tracing::info;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use winit::{
     application::ApplicationHandler,
-    event::WindowEvent,
+    event::{DeviceEvent, DeviceId, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::{Window, WindowId},
 };
 
+use crate::config::{Config, CONFIG_FILE};
+use crate::error::{CuborumError, Result};
+use crate::renderer::camera_path::CameraPresets;
+use crate::renderer::shaders::ShaderSource;
 use crate::renderer::state::State;
+use crate::replay::{InputRecorder, RecordingHeader, Replay};
+use crate::simulation::Simulation;
 
 struct App {
+    config: Config,
+    presets: CameraPresets,
+    presets_file: PathBuf,
+    recorder: Option<InputRecorder>,
+    replay: Option<Replay>,
+    shaders: ShaderSource,
     state: Option<State>,
+    error: Option<CuborumError>, // Из-за чего цикл событий остановлен
 }
 
 impl App {
-    async fn new(window: Arc<Window>) -> Self {
-        let state = State::new(window.clone()).await;
-        Self {
-            state: Some(state),
-        }
+    fn create_state(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        let window = Arc::new(event_loop.create_window(Window::default_attributes().with_title("Cuborum MVP"))?);
+
+        // Запись воспроизводится с теми настройками и закладками, с которыми была сделана
+        let (config, presets) = match &self.replay {
+            Some(replay) => (replay.header.config(), replay.header.presets.clone()),
+            None => (self.config.clone(), self.presets.clone()),
+        };
+        let mut sim = Simulation::with_test_pattern(&config);
+        sim.presets = presets;
+        let mut state = pollster::block_on(State::new(window.clone(), sim, self.shaders.clone(), self.config.graphics.msaa))?;
+        state.presets_file = Some(self.presets_file.clone());
+        state.recorder = self.recorder.take();
+        state.replay = self.replay.take();
+        self.state = Some(state);
+
+        window.request_redraw();
+        Ok(())
+    }
+
+    fn fail(&mut self, event_loop: &ActiveEventLoop, err: CuborumError) {
+        self.error = Some(err);
+        event_loop.exit();
     }
 }
 
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.state.is_none() {
-            let window = Arc::new(
-                event_loop.create_window(Window::default_attributes()).unwrap(),
-            );
-
-            let state = pollster::block_on(State::new(window.clone()));
-            self.state = Some(state);
+            if let Err(err) = self.create_state(event_loop) {
+                self.fail(event_loop, err);
+            }
+        }
+    }
 
-            window.request_redraw();
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _id: DeviceId, event: DeviceEvent) {
+        if let Some(state) = &mut self.state {
+            state.device_input(&event);
         }
     }
 
@@ -46,28 +78,57 @@ impl ApplicationHandler for App {
                 }
                 WindowEvent::RedrawRequested => {
                     state.update(); // ✅ Теперь обновляем камеру перед рендерингом
-                    state.render();
+                    if let Err(err) = state.render() {
+                        self.fail(event_loop, err);
+                        return;
+                    }
                     state.get_window().request_redraw();
                 }
                 WindowEvent::Resized(size) => {
                     state.resize(size);
                 }
+                // Следом придёт Resized с новым размером в пикселях
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    state.set_scale_factor(scale_factor);
+                }
                 _ => (),
             }
         }
+
+        // Устройство потеряно (сброс драйвера, отключение GPU): собираем всё заново из сетки на CPU
+        if self.state.as_ref().is_some_and(State::is_device_lost) {
+            if let Some(state) = self.state.take() {
+                match pollster::block_on(state.recreate()) {
+                    Ok(state) => self.state = Some(state),
+                    Err(err) => self.fail(event_loop, err),
+                }
+            }
+        }
     }
 }
 
-pub async fn run() {
-    let event_loop = EventLoop::new().unwrap();
+/// Открывает окно и крутит цикл событий до закрытия или ошибки
+pub async fn run(record: Option<PathBuf>, replay: Option<PathBuf>, shaders: ShaderSource) -> Result<()> {
+    let event_loop = EventLoop::new()?;
     event_loop.set_control_flow(ControlFlow::Wait);
 
-    let window = Arc::new(
-        event_loop
-            .create_window(Window::default_attributes().with_title("Cuborum MVP"))
-            .unwrap(),
-    );
+    let config = Config::load(Path::new(CONFIG_FILE));
+    let presets_file = CameraPresets::file_for(Path::new(CONFIG_FILE));
+    let presets = CameraPresets::load(&presets_file);
+
+    let recorder = match record {
+        Some(path) => Some(
+            InputRecorder::create(&path, &RecordingHeader::new(&config, &presets))
+                .map_err(|source| CuborumError::Recording { path, source })?,
+        ),
+        None => None,
+    };
+    let replay = match replay {
+        Some(path) => Some(Replay::load(&path).map_err(|source| CuborumError::Replay { path, source })?),
+        None => None,
+    };
 
-    let mut app = App::new(window).await;
-    event_loop.run_app(&mut app).unwrap();
+    let mut app = App { config, presets, presets_file, recorder, replay, shaders, state: None, error: None };
+    event_loop.run_app(&mut app)?;
+    app.error.map_or(Ok(()), Err)
 }
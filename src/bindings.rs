@@ -0,0 +1,336 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use winit::keyboard::KeyCode;
+
+/// Действия, на которые назначаются клавиши
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // Удерживаемые
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,   // Вверх
+    Crouch, // Вниз
+    Sprint,
+    // Срабатывают при нажатии
+    Reset,
+    ReleaseMouse,
+    ToggleCameraMode,
+    FrameGrid,
+    CycleProjection,
+    ViewTop,
+    ViewFront,
+    ViewSide,
+    CycleRenderPath,
+    ProgressiveRender,
+    ToggleAmbientOcclusion,
+    ToggleLod,
+    ToggleLodColor,
+    SaveConfig,
+    DumpRenderGraph,
+    CycleTonemap,
+    ToggleBloom,
+    ToggleFxaa,
+    IncreaseExposure,
+    DecreaseExposure,
+    CycleMsaa,
+    AddBookmark,
+    NextBookmark,
+    AddKeyframe,
+    ClearPath,
+    PlayPath,
+}
+
+impl Action {
+    /// Действие срабатывает один раз при нажатии, а не пока клавиша зажата
+    pub fn is_trigger(self) -> bool {
+        !matches!(
+            self,
+            Action::MoveForward
+                | Action::MoveBackward
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::Jump
+                | Action::Crouch
+                | Action::Sprint
+        )
+    }
+}
+
+/// Модификаторы сочетания
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+/// Физическая клавиша с модификаторами, не зависит от раскладки.
+/// В конфиге записывается строкой вида `"Ctrl+KeyS"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl KeyBinding {
+    pub const fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            modifiers: Modifiers { ctrl: false, shift: false, alt: false },
+        }
+    }
+
+    pub const fn ctrl(key: KeyCode) -> Self {
+        Self {
+            key,
+            modifiers: Modifiers { ctrl: true, shift: false, alt: false },
+        }
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.modifiers.ctrl, "Ctrl"), (self.modifiers.shift, "Shift"), (self.modifiers.alt, "Alt")] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        // Debug-имя совпадает с именем варианта в serde
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(binding: KeyBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop().unwrap_or_default();
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                _ => return Err(format!("unknown modifier `{part}` in `{text}`")),
+            }
+        }
+        let key = KeyCode::deserialize(StrDeserializer::<ValueError>::new(key_name))
+            .map_err(|_| format!("unknown key `{key_name}` in `{text}`"))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+/// Назначения клавиш: у действия может быть несколько сочетаний
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Bindings(pub BTreeMap<Action, Vec<KeyBinding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Action::*;
+        let defaults = [
+            (MoveForward, KeyBinding::key(KeyCode::KeyW)),
+            (MoveBackward, KeyBinding::key(KeyCode::KeyS)),
+            (MoveLeft, KeyBinding::key(KeyCode::KeyA)),
+            (MoveRight, KeyBinding::key(KeyCode::KeyD)),
+            (Jump, KeyBinding::key(KeyCode::Space)),
+            (Crouch, KeyBinding::key(KeyCode::ControlLeft)),
+            (Sprint, KeyBinding::key(KeyCode::ShiftLeft)),
+            (Reset, KeyBinding::key(KeyCode::KeyR)),
+            (ReleaseMouse, KeyBinding::key(KeyCode::Escape)),
+            (ToggleCameraMode, KeyBinding::key(KeyCode::Tab)),
+            (FrameGrid, KeyBinding::key(KeyCode::KeyF)),
+            (CycleProjection, KeyBinding::key(KeyCode::KeyC)),
+            (ViewTop, KeyBinding::key(KeyCode::Numpad7)),
+            (ViewFront, KeyBinding::key(KeyCode::Numpad1)),
+            (ViewSide, KeyBinding::key(KeyCode::Numpad3)),
+            (CycleRenderPath, KeyBinding::key(KeyCode::KeyV)),
+            (ProgressiveRender, KeyBinding::key(KeyCode::KeyP)),
+            (ToggleAmbientOcclusion, KeyBinding::key(KeyCode::KeyO)),
+            (ToggleLod, KeyBinding::key(KeyCode::KeyL)),
+            (ToggleLodColor, KeyBinding::key(KeyCode::KeyM)),
+            (SaveConfig, KeyBinding::ctrl(KeyCode::KeyS)),
+            (DumpRenderGraph, KeyBinding::key(KeyCode::F9)),
+            (CycleTonemap, KeyBinding::key(KeyCode::KeyT)),
+            (ToggleBloom, KeyBinding::key(KeyCode::KeyG)),
+            (ToggleFxaa, KeyBinding::key(KeyCode::KeyX)),
+            (IncreaseExposure, KeyBinding::key(KeyCode::Equal)),
+            (DecreaseExposure, KeyBinding::key(KeyCode::Minus)),
+            (CycleMsaa, KeyBinding::key(KeyCode::KeyN)),
+            (AddBookmark, KeyBinding::ctrl(KeyCode::KeyB)),
+            (NextBookmark, KeyBinding::key(KeyCode::KeyB)),
+            (AddKeyframe, KeyBinding::key(KeyCode::KeyK)),
+            (ClearPath, KeyBinding::ctrl(KeyCode::KeyK)),
+            (PlayPath, KeyBinding::key(KeyCode::Enter)),
+        ];
+        Self(defaults.into_iter().map(|(action, binding)| (action, vec![binding])).collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for Bindings {
+    /// Действия, которых нет в файле, получают назначения по умолчанию
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = BTreeMap::<Action, Vec<KeyBinding>>::deserialize(deserializer)?;
+        for (action, bindings) in Bindings::default().0 {
+            map.entry(action).or_insert(bindings);
+        }
+        Ok(Self(map))
+    }
+}
+
+/// Нажатые физические клавиши и их сопоставление с действиями
+#[derive(Default)]
+pub struct Controls {
+    pub bindings: Bindings,
+    pub pressed: HashSet<KeyCode>,
+}
+
+impl Controls {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            pressed: HashSet::new(),
+        }
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.pressed.insert(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.pressed.remove(&key);
+    }
+
+    /// Окно потеряло фокус – отпускания клавиш мы уже не увидим
+    pub fn release_all(&mut self) {
+        self.pressed.clear();
+    }
+
+    /// Модификаторы по зажатым клавишам
+    pub fn modifiers(&self) -> Modifiers {
+        let held = |keys: [KeyCode; 2]| keys.iter().any(|key| self.pressed.contains(key));
+        Modifiers {
+            ctrl: held([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: held([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: held([KeyCode::AltLeft, KeyCode::AltRight]),
+        }
+    }
+
+    fn bindings_of(&self, action: Action) -> &[KeyBinding] {
+        self.bindings.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Клавиши сочетаний с модификаторами, которые сейчас зажаты целиком (Ctrl+S),
+    /// вместе с самими модификаторами
+    fn chord_keys(&self) -> HashSet<KeyCode> {
+        let modifiers = self.modifiers();
+        if modifiers == Modifiers::default() {
+            return HashSet::new();
+        }
+        let mut keys: HashSet<KeyCode> =
+            self.pressed.iter().copied().filter(|&key| self.is_trigger_chord(key, modifiers)).collect();
+        if !keys.is_empty() {
+            let modifier_keys = [
+                (modifiers.ctrl, [KeyCode::ControlLeft, KeyCode::ControlRight]),
+                (modifiers.shift, [KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+                (modifiers.alt, [KeyCode::AltLeft, KeyCode::AltRight]),
+            ];
+            keys.extend(modifier_keys.into_iter().filter(|(held, _)| *held).flat_map(|(_, keys)| keys));
+        }
+        keys
+    }
+
+    fn is_trigger_chord(&self, key: KeyCode, modifiers: Modifiers) -> bool {
+        self.bindings.0.iter().any(|(action, bindings)| {
+            action.is_trigger() && bindings.iter().any(|b| b.key == key && b.modifiers == modifiers)
+        })
+    }
+
+    /// Удерживается ли действие. Лишние модификаторы не мешают:
+    /// с зажатым Shift (спринт) W всё ещё ведёт вперёд. Клавиши зажатого сочетания
+    /// действия-триггера не удерживают ничего: Ctrl+S не приседает и не пятится.
+    pub fn is_held(&self, action: Action) -> bool {
+        let modifiers = self.modifiers();
+        let chord_keys = self.chord_keys();
+        self.bindings_of(action).iter().any(|binding| {
+            self.pressed.contains(&binding.key)
+                && !chord_keys.contains(&binding.key)
+                && (!binding.modifiers.ctrl || modifiers.ctrl)
+                && (!binding.modifiers.shift || modifiers.shift)
+                && (!binding.modifiers.alt || modifiers.alt)
+        })
+    }
+
+    /// Действие, которое срабатывает от нажатия `key`. Модификаторы должны совпасть
+    /// точно, чтобы Ctrl+S и S были разными действиями.
+    pub fn triggered(&self, key: KeyCode) -> Option<Action> {
+        let modifiers = self.modifiers();
+        self.bindings
+            .0
+            .iter()
+            .filter(|(action, _)| action.is_trigger())
+            .find(|(_, bindings)| bindings.iter().any(|b| b.key == key && b.modifiers == modifiers))
+            .map(|(&action, _)| action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_round_trips_through_text() {
+        let binding = KeyBinding::ctrl(KeyCode::KeyS);
+        assert_eq!(binding.to_string(), "Ctrl+KeyS");
+        assert_eq!(KeyBinding::try_from("ctrl + KeyS".to_string()), Ok(binding));
+        assert!(KeyBinding::try_from("Hyper+KeyS".to_string()).is_err());
+        assert!(KeyBinding::try_from("KeyЦ".to_string()).is_err());
+    }
+
+    #[test]
+    fn modifiers_separate_triggers_and_chords_hold_nothing() {
+        let mut controls = Controls::default();
+        controls.press(KeyCode::KeyS);
+        assert_eq!(controls.triggered(KeyCode::KeyS), None); // S – только удерживаемое
+        controls.press(KeyCode::ControlLeft);
+        assert_eq!(controls.triggered(KeyCode::KeyS), Some(Action::SaveConfig));
+        assert!(!controls.is_held(Action::MoveBackward));
+        assert!(!controls.is_held(Action::Crouch));
+        controls.release(KeyCode::KeyS);
+        assert!(controls.is_held(Action::Crouch));
+
+        // Shift+W – не сочетание триггера, спринт вперёд
+        controls.release_all();
+        controls.press(KeyCode::ShiftLeft);
+        controls.press(KeyCode::KeyW);
+        assert!(controls.is_held(Action::MoveForward));
+        assert!(controls.is_held(Action::Sprint));
+    }
+
+    #[test]
+    fn missing_actions_keep_defaults() {
+        #[derive(Deserialize)]
+        struct File {
+            bindings: Bindings,
+        }
+        let file: File = toml::from_str("[bindings]\nmove_forward = [\"ArrowUp\", \"KeyZ\"]\n").unwrap();
+        assert_eq!(
+            file.bindings.0[&Action::MoveForward],
+            vec![KeyBinding::key(KeyCode::ArrowUp), KeyBinding::key(KeyCode::KeyZ)]
+        );
+        assert_eq!(file.bindings.0[&Action::MoveLeft], vec![KeyBinding::key(KeyCode::KeyA)]);
+    }
+}
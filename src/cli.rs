@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+/// Что запускать: окно или один из безоконных режимов
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Окно; ввод можно записать в файл или воспроизвести из файла
+    Window {
+        record: Option<PathBuf>,
+        replay: Option<PathBuf>,
+        shader_dir: Option<PathBuf>,
+    },
+    Progressive(Progressive),
+}
+
+/// Прогрессивная трассировка путей без окна с сохранением PNG
+/// (если задана запись – после её воспроизведения).
+/// С путём камеры `output` – каталог, в него пишутся кадры пролёта.
+#[derive(Debug, PartialEq)]
+pub struct Progressive {
+    pub samples: u32,
+    pub width: u32,
+    pub height: u32,
+    pub output: PathBuf,
+    pub replay: Option<PathBuf>,
+    pub camera_path: Option<String>,
+    pub fps: u32,
+    pub shader_dir: Option<PathBuf>, // Шейдеры из каталога вместо вшитых
+}
+
+pub const USAGE: &str = "usage: cuborum [--record <input.jsonl> | --replay <input.jsonl>] \
+[--progressive <samples>] [--size <width>x<height>] [--output <file.png>] \
+[--camera-path <name> [--fps <n>]] [--shader-dir <dir>]";
+
+/// Разбирает аргументы командной строки (без имени программы)
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut samples = None;
+    let mut size = (640, 480);
+    let mut output = PathBuf::from("pathtrace.png");
+    let mut record = None;
+    let mut replay = None;
+    let mut camera_path = None;
+    let mut fps = 30;
+    let mut shader_dir = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--progressive" => {
+                let value = value()?;
+                samples = Some(value.parse().map_err(|_| format!("invalid sample count: {value}"))?);
+            }
+            "--size" => {
+                let value = value()?;
+                size = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .filter(|&(w, h)| w > 0 && h > 0)
+                    .ok_or_else(|| format!("invalid size: {value}"))?;
+            }
+            "--output" => output = PathBuf::from(value()?),
+            "--record" => record = Some(PathBuf::from(value()?)),
+            "--replay" => replay = Some(PathBuf::from(value()?)),
+            "--camera-path" => camera_path = Some(value()?),
+            "--shader-dir" => shader_dir = Some(PathBuf::from(value()?)),
+            "--fps" => {
+                let value = value()?;
+                fps = value
+                    .parse()
+                    .ok()
+                    .filter(|&fps| fps > 0)
+                    .ok_or_else(|| format!("invalid frame rate: {value}"))?;
+            }
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    if record.is_some() && (replay.is_some() || samples.is_some()) {
+        return Err("--record works only in the window without --replay".to_string());
+    }
+    if camera_path.is_some() && samples.is_none() {
+        return Err("--camera-path works only with --progressive".to_string());
+    }
+
+    Ok(match samples {
+        Some(samples) => Command::Progressive(Progressive {
+            samples,
+            width: size.0,
+            height: size.1,
+            output,
+            replay,
+            camera_path,
+            fps,
+            shader_dir,
+        }),
+        None => Command::Window { record, replay, shader_dir },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &str) -> Result<Command, String> {
+        parse(args.split_whitespace().map(str::to_string))
+    }
+
+    fn window(record: Option<&str>, replay: Option<&str>, shader_dir: Option<&str>) -> Command {
+        Command::Window {
+            record: record.map(PathBuf::from),
+            replay: replay.map(PathBuf::from),
+            shader_dir: shader_dir.map(PathBuf::from),
+        }
+    }
+
+    fn progressive(samples: u32) -> Progressive {
+        Progressive {
+            samples,
+            width: 640,
+            height: 480,
+            output: PathBuf::from("pathtrace.png"),
+            replay: None,
+            camera_path: None,
+            fps: 30,
+            shader_dir: None,
+        }
+    }
+
+    #[test]
+    fn window_options() {
+        let cases = [
+            ("", window(None, None, None)),
+            ("--record in.jsonl", window(Some("in.jsonl"), None, None)),
+            ("--replay in.jsonl", window(None, Some("in.jsonl"), None)),
+            ("--shader-dir shaders", window(None, None, Some("shaders"))),
+            ("--replay in.jsonl --shader-dir s", window(None, Some("in.jsonl"), Some("s"))),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(run(args), Ok(expected), "{args}");
+        }
+    }
+
+    #[test]
+    fn progressive_options() {
+        let cases = [
+            ("--progressive 8", progressive(8)),
+            ("--progressive 8 --size 64x48", Progressive { width: 64, height: 48, ..progressive(8) }),
+            ("--progressive 8 --output x.png", Progressive { output: PathBuf::from("x.png"), ..progressive(8) }),
+            ("--progressive 8 --replay in.jsonl", Progressive { replay: Some(PathBuf::from("in.jsonl")), ..progressive(8) }),
+            ("--progressive 8 --camera-path tour", Progressive { camera_path: Some("tour".to_string()), ..progressive(8) }),
+            (
+                "--camera-path tour --fps 60 --progressive 8",
+                Progressive { camera_path: Some("tour".to_string()), fps: 60, ..progressive(8) },
+            ),
+            ("--progressive 8 --shader-dir s", Progressive { shader_dir: Some(PathBuf::from("s")), ..progressive(8) }),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(run(args), Ok(Command::Progressive(expected)), "{args}");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("--record", "missing value for --record"),
+            ("--progressive 4 --output", "missing value for --output"),
+            ("--verbose", "unknown argument: --verbose"),
+            ("--progressive many", "invalid sample count: many"),
+            ("--progressive 4 --size 64", "invalid size: 64"),
+            ("--progressive 4 --size 0x48", "invalid size: 0x48"),
+            ("--progressive 4 --fps 0", "invalid frame rate: 0"),
+            ("--record a --replay b", "--record works only in the window without --replay"),
+            ("--record a --progressive 4", "--record works only in the window without --replay"),
+            ("--camera-path tour", "--camera-path works only with --progressive"),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(run(args), Err(expected.to_string()), "{args}");
+        }
+    }
+}
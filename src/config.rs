@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Bindings;
+
+/// Файл настроек по умолчанию (в рабочем каталоге)
+pub const CONFIG_FILE: &str = "cuborum.toml";
+
+/// Настройки мыши
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseConfig {
+    pub sensitivity: f32, // Градусов поворота на единицу смещения мыши
+    pub invert_y: bool,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.1,
+            invert_y: false,
+        }
+    }
+}
+
+/// Настройки графики
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsConfig {
+    pub msaa: u32, // Сэмплов на пиксель: 1, 2, 4 или 8 (сколько умеет адаптер)
+}
+
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self { msaa: 4 }
+    }
+}
+
+/// Пользовательские настройки, читаются из TOML
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub mouse: MouseConfig,
+    pub bindings: Bindings,
+    pub graphics: GraphicsConfig,
+}
+
+impl Config {
+    /// Читает настройки; если файла нет или он испорчен – настройки по умолчанию
+    pub fn load(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                tracing::warn!("Failed to read {}: {}", path.display(), err);
+                return Self::default();
+            }
+        };
+        toml::from_str(&text).unwrap_or_else(|err| {
+            tracing::warn!("Failed to parse {}: {}", path.display(), err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let text = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config: Config = toml::from_str("[mouse]\ninvert_y = true\n").unwrap();
+        assert!(config.mouse.invert_y);
+        assert_eq!(config.mouse.sensitivity, MouseConfig::default().sensitivity);
+        assert_eq!(config.bindings, Bindings::default());
+        assert_eq!(config.graphics.msaa, GraphicsConfig::default().msaa);
+    }
+
+    #[test]
+    fn saved_config_loads_back() {
+        let mut config = Config::default();
+        config.mouse.invert_y = true;
+        let text = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&text).unwrap();
+        assert!(loaded.mouse.invert_y);
+        assert_eq!(loaded.bindings, config.bindings);
+    }
+}
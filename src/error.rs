@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+/// Ошибки запуска и инициализации рендера
+#[derive(Debug, thiserror::Error)]
+pub enum CuborumError {
+    #[error("no suitable GPU adapter found")]
+    Adapter,
+    #[error("failed to create GPU device: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+    #[error("failed to create surface: {0}")]
+    Surface(#[from] wgpu::CreateSurfaceError),
+    #[error("surface is not supported by the adapter")]
+    SurfaceUnsupported,
+    #[error("failed to present frame: {0}")]
+    Present(#[from] wgpu::SurfaceError),
+    #[error("failed to create window: {0}")]
+    Window(#[from] winit::error::OsError),
+    #[error("event loop failed: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+    #[error("failed to read shader {}: {source}", path.display())]
+    ShaderIo {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to preprocess shader {label}: {message}")]
+    ShaderPreprocess { label: String, message: String },
+    /// Текст ошибки валидации wgpu (naga), с номерами строк
+    #[error("failed to compile shader {label}:\n{message}")]
+    ShaderCompile { label: String, message: String },
+    #[error("failed to create pipeline {label}:\n{message}")]
+    Pipeline { label: String, message: String },
+    #[error("render graph has a cycle between passes {}", passes.join(", "))]
+    RenderGraphCycle { passes: Vec<&'static str> },
+    #[error("failed to create recording {}: {source}", path.display())]
+    Recording {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to load recording {}: {source}", path.display())]
+    Replay {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to create output directory {}: {source}", path.display())]
+    OutputDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to read back {label} from the GPU: {source}")]
+    Readback {
+        label: &'static str,
+        source: wgpu::BufferAsyncError,
+    },
+    #[error("failed to save image {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        source: png::EncodingError,
+    },
+    #[error("no camera path `{name}` in {}", file.display())]
+    CameraPath { name: String, file: PathBuf },
+}
+
+pub type Result<T> = std::result::Result<T, CuborumError>;
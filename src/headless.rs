@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use wgpu::util::DeviceExt;
+use wgpu::{Backends, Instance, InstanceDescriptor, InstanceFlags, PowerPreference, RequestAdapterOptions};
+use winit::dpi::PhysicalSize;
+
+use crate::cli::Progressive;
+use crate::config::{Config, CONFIG_FILE};
+use crate::error::{CuborumError, Result};
+use crate::renderer::camera::Camera;
+use crate::renderer::camera_path::CameraPresets;
+use crate::renderer::pathtrace::PathTracer;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::post::PostProcess;
+use crate::renderer::shaders::ShaderSource;
+use crate::renderer::shadow::Sun;
+use crate::renderer::texture::{SceneTarget, LDR_FORMAT};
+use crate::replay::Replay;
+use crate::simulation::{Simulation, FIXED_DT};
+
+/// Прогоняет запись ввода без окна, шаг за шагом без ожидания реального времени
+pub fn replay_simulation(path: &Path) -> Result<Simulation> {
+    let mut replay = Replay::load(path).map_err(|source| CuborumError::Replay { path: path.into(), source })?;
+    let mut sim = Simulation::with_test_pattern(&replay.header.config());
+    sim.presets = replay.header.presets.clone();
+    // После последнего события даём секунду на торможение и затухание орбиты
+    let end_tick = replay.last_tick() + (1.0 / FIXED_DT) as u64;
+    while sim.tick <= end_tick {
+        for event in replay.take(sim.tick) {
+            sim.apply(&event);
+        }
+        sim.step();
+    }
+    tracing::info!(
+        "Replayed {} ticks, camera at {:?} looking {:?}",
+        sim.tick,
+        sim.camera.position,
+        sim.camera.direction
+    );
+    Ok(sim)
+}
+
+/// Тестовая сцена или результат воспроизведения записи
+fn simulation(replay: Option<&Path>) -> Result<Simulation> {
+    match replay {
+        Some(path) => replay_simulation(path),
+        None => Ok(Simulation::with_test_pattern(&Config::default())),
+    }
+}
+
+/// Трассировщик путей без окна на программном адаптере
+struct HeadlessRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pathtracer: PathTracer,
+    post: PostProcess,
+    sun: Sun,
+}
+
+impl HeadlessRenderer {
+    async fn new(sim: &Simulation, shaders: &ShaderSource, width: u32, height: u32) -> Result<Self> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::VULKAN | Backends::GL,
+            flags: InstanceFlags::default(),
+            backend_options: Default::default(),
+        });
+
+        let adapter = instance.request_adapter(&RequestAdapterOptions {
+            power_preference: PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: true,
+        }).await.ok_or(CuborumError::Adapter)?;
+
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Cuborum Headless Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+                memory_hints: wgpu::MemoryHints::default(),
+            },
+            None,
+        ).await?;
+
+        let voxel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Voxel Buffer"),
+            contents: bytemuck::cast_slice(&sim.voxel_grid.data),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&sim.light_volume.packed()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let grid_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Uniform Buffer"),
+            contents: bytemuck::bytes_of(&sim.voxel_grid.uniform()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // Цель вывода на экран здесь не используется, изображение читается из буфера накопления
+        let mut pipelines = PipelineCache::new(&device, shaders.clone());
+        let pathtracer = PathTracer::new(
+            &device,
+            &mut pipelines,
+            SceneTarget::hdr(1),
+            PhysicalSize::new(width, height),
+            &voxel_buffer,
+            &light_buffer,
+            &grid_buffer,
+        )?;
+        let post = PostProcess::new(&device, &mut pipelines, LDR_FORMAT)?;
+
+        Ok(Self { device, queue, pathtracer, post, sun: Sun::default() })
+    }
+
+    /// Копит `samples` сэмплов с нуля для вида `camera` и сохраняет PNG
+    fn render(&mut self, camera: &Camera, samples: u32, output: &Path) -> Result<()> {
+        self.pathtracer.reset();
+        while self.pathtracer.samples() < samples {
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            self.pathtracer.dispatch(&self.queue, &mut encoder, camera, &self.sun);
+            self.queue.submit([encoder.finish()]);
+            self.device.poll(wgpu::Maintain::Wait);
+        }
+        self.pathtracer.save_png(&self.device, &self.queue, &self.post, 1, output)
+    }
+}
+
+/// Трассирует тестовую сцену без окна, пока не наберётся `samples` сэмплов, и сохраняет PNG.
+/// Если задана запись ввода, сначала воспроизводит её и рендерит получившийся вид.
+/// Работает на программном адаптере, поэтому подходит для CI.
+pub async fn run_progressive(options: &Progressive, shaders: &ShaderSource) -> Result<()> {
+    let mut sim = simulation(options.replay.as_deref())?;
+    sim.camera.aspect_ratio = options.width as f32 / options.height as f32;
+
+    let mut renderer = HeadlessRenderer::new(&sim, shaders, options.width, options.height).await?;
+    renderer.render(&sim.camera, options.samples, &options.output)?;
+    tracing::info!("Progressive render saved to {} ({} spp)", options.output.display(), options.samples);
+    Ok(())
+}
+
+/// Рендерит пролёт по пути камеры `name` из файла путей: `fps` кадров в секунду,
+/// каждый кадр – `samples` сэмплов, файлы `frame_0000.png`… в каталоге `output`
+pub async fn run_camera_path(options: &Progressive, name: &str, shaders: &ShaderSource) -> Result<()> {
+    let Progressive { samples, width, height, fps, .. } = *options;
+    let output = options.output.as_path();
+    let file = CameraPresets::file_for(Path::new(CONFIG_FILE));
+    let presets = CameraPresets::load(&file);
+    let path = presets.path(name).ok_or_else(|| CuborumError::CameraPath { name: name.to_string(), file })?;
+    let mut sim = simulation(options.replay.as_deref())?;
+    sim.camera.aspect_ratio = width as f32 / height as f32;
+    std::fs::create_dir_all(output).map_err(|source| CuborumError::OutputDir { path: output.into(), source })?;
+
+    let mut renderer = HeadlessRenderer::new(&sim, shaders, width, height).await?;
+    let frames = (path.duration() * fps as f32).floor() as u32 + 1;
+    for frame in 0..frames {
+        if let Some(pose) = path.sample(frame as f32 / fps as f32) {
+            pose.apply(&mut sim.camera);
+        }
+        renderer.render(&sim.camera, samples, &output.join(format!("frame_{frame:04}.png")))?;
+    }
+    tracing::info!("Camera path `{}` rendered to {} ({} frames)", name, output.display(), frames);
+    Ok(())
+}
@@ -1,15 +1,54 @@
-use tracing::{info};
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing::info;
+use std::path::PathBuf;
+
+use tracing_subscriber::EnvFilter;
 
 mod app;
+mod bindings;
+mod cli;
+mod config;
+mod error;
+mod headless;
 mod renderer;
+mod replay;
+mod simulation;
+
+use renderer::shaders::ShaderSource;
 
 fn main() {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
+    let command = match cli::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     info!("Cuborum MVP started.");
 
-    pollster::block_on(app::run());
+    let result = match command {
+        cli::Command::Window { record, replay, shader_dir } => {
+            pollster::block_on(app::run(record, replay, shader_source(shader_dir)))
+        }
+        cli::Command::Progressive(options) => {
+            let shaders = shader_source(options.shader_dir.clone());
+            match &options.camera_path {
+                Some(name) => pollster::block_on(headless::run_camera_path(&options, name, &shaders)),
+                None => pollster::block_on(headless::run_progressive(&options, &shaders)),
+            }
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+/// Вшитые шейдеры или, для правки на лету, файлы из `--shader-dir`
+fn shader_source(shader_dir: Option<PathBuf>) -> ShaderSource {
+    shader_dir.map_or(ShaderSource::Embedded, ShaderSource::Directory)
 }
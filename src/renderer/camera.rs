@@ -1,6 +1,56 @@
-use nalgebra::{Matrix4, Perspective3, Point3, Vector3};
-use std::collections::HashSet;
-use winit::keyboard::Key;
+use nalgebra::{Matrix4, Orthographic3, Perspective3, Point3, Vector3, Vector4};
+use crate::bindings::{Action, Controls};
+use crate::renderer::frustum::{Aabb, Frustum};
+use crate::renderer::orbit::{Orbit, MIN_DISTANCE};
+use crate::renderer::pipeline::common::wgsl_struct;
+
+/// Во сколько раз быстрее летим с зажатым Shift
+const SPRINT_MULTIPLIER: f32 = 3.0;
+/// Насколько быстро скорость догоняет целевую (1/с): разгон и торможение
+const ACCELERATION: f32 = 10.0;
+/// Пределы скорости, настраиваемой колесом (единиц в секунду)
+const MIN_SPEED: f32 = 0.1;
+const MAX_SPEED: f32 = 50.0;
+/// Множитель скорости за одно деление колеса
+const SPEED_STEP: f32 = 1.1;
+/// Во сколько раз приближает ортографический вид одно деление колеса
+const ORTHO_ZOOM_STEP: f32 = 1.1;
+/// Наименьшая половина высоты ортографического вида
+const MIN_ORTHO_HEIGHT: f32 = 0.01;
+/// Наклон изометрического вида: диагональ куба идёт в глубину экрана
+const ISOMETRIC_PITCH: f32 = -35.264_39;
+
+/// nalgebra строит проекции в OpenGL-конвенции (z в [-1, 1]), wgpu ожидает z в [0, 1]
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.5,
+    0.0, 0.0, 0.0, 1.0,
+);
+
+/// Проекция камеры
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    Orthographic, // Масштаб задаёт `ortho_height`
+    Isometric,    // Ортографическая с фиксированными углами, вращение мышью отключено
+}
+
+/// Виды вдоль осей для редактирования (ортографические)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxisView {
+    Top,   // Сверху вниз, -Y
+    Front, // Вдоль -Z
+    Side,  // Вдоль -X
+}
+
+/// Способ управления камерой
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    FreeFly, // WASD + взгляд мышью
+    Orbit,   // Вращение вокруг цели, колесо – приближение
+}
 
 pub struct Camera {
     pub position: Point3<f32>,
@@ -12,11 +62,14 @@ pub struct Camera {
     pub far: f32,
     pub yaw: f32,   // Горизонтальный угол
     pub pitch: f32, // Вертикальный угол
-    pub speed: f32,  // Скорость движения
-    pub sensitivity: f32, // Чувствительность мыши
-    pub last_cursor_x: f32,
-    pub last_cursor_y: f32,
-    pub pressed_keys: HashSet<Key>, // ✅ Теперь храним нажатые клавиши
+    pub speed: f32,  // Скорость движения, единиц в секунду
+    pub velocity: Vector3<f32>, // Текущая скорость (плавно догоняет целевую)
+    pub sensitivity: f32, // Градусов поворота на единицу смещения мыши
+    pub invert_y: bool,
+    pub mode: CameraMode,
+    pub orbit: Orbit,
+    pub projection: Projection,
+    pub ortho_height: f32, // Половина видимой высоты в ортографических проекциях
 }
 
 impl Camera {
@@ -28,66 +81,104 @@ impl Camera {
             aspect_ratio,
             fov: 45.0_f32.to_radians(),
             near: 0.1,
-            far: 100.0,
+            far: 500.0, // Дальние чанки рисуются грубыми LOD
             yaw: -90.0, // Теперь смотрим вдоль -Z
             pitch: 0.0,
-            sensitivity: 0.05, // ✅ Чувствительность мыши
-            speed: 0.02, // ✅ Чувствительность мыши
-            last_cursor_x: 0.0,
-            last_cursor_y: 0.0,
-            pressed_keys: HashSet::new(),
+            sensitivity: 0.1,
+            invert_y: false,
+            speed: 1.5,
+            velocity: Vector3::zeros(),
+            mode: CameraMode::FreeFly,
+            orbit: Orbit::default(),
+            projection: Projection::Perspective,
+            ortho_height: 1.0,
         }
     }
 
+    /// Вправо от взгляда; считается по yaw, поэтому определено и при взгляде строго вниз
+    pub fn right(&self) -> Vector3<f32> {
+        let yaw = self.yaw.to_radians();
+        Vector3::new(-yaw.sin(), 0.0, yaw.cos())
+    }
+
     pub fn view_matrix(&self) -> Matrix4<f32> {
-        Matrix4::look_at_rh(&self.position, &(self.position + self.direction), &self.up)
+        let up = self.right().cross(&self.direction);
+        Matrix4::look_at_rh(&self.position, &(self.position + self.direction), &up)
     }
 
     pub fn projection_matrix(&self) -> Matrix4<f32> {
-        Perspective3::new(self.aspect_ratio, self.fov, self.near, self.far).to_homogeneous()
+        self.projection_matrix_between(self.near, self.far)
+    }
+
+    /// Проекция с другими плоскостями отсечения (для каскадов теней)
+    pub fn projection_matrix_between(&self, near: f32, far: f32) -> Matrix4<f32> {
+        let projection = match self.projection {
+            Projection::Perspective => Perspective3::new(self.aspect_ratio, self.fov, near, far).to_homogeneous(),
+            Projection::Orthographic | Projection::Isometric => {
+                let (half_width, half_height) = (self.ortho_height * self.aspect_ratio, self.ortho_height);
+                Orthographic3::new(-half_width, half_width, -half_height, half_height, near, far).to_homogeneous()
+            }
+        };
+        OPENGL_TO_WGPU_MATRIX * projection
     }
 
-    pub fn process_keyboard(&mut self) {
+    pub fn process_keyboard(&mut self, controls: &Controls, dt: f32) {
         let mut move_dir = Vector3::zeros();
 
-        if self.pressed_keys.contains(&Key::Character("w".into())) {
+        if controls.is_held(Action::MoveForward) {
             move_dir += self.direction;
         }
-        if self.pressed_keys.contains(&Key::Character("s".into())) {
+        if controls.is_held(Action::MoveBackward) {
             move_dir -= self.direction;
         }
-        if self.pressed_keys.contains(&Key::Character("a".into())) {
-            move_dir -= self.direction.cross(&self.up).normalize();
+        if controls.is_held(Action::MoveLeft) {
+            move_dir -= self.right();
         }
-        if self.pressed_keys.contains(&Key::Character("d".into())) {
-            move_dir += self.direction.cross(&self.up).normalize();
+        if controls.is_held(Action::MoveRight) {
+            move_dir += self.right();
         }
-        if self.pressed_keys.contains(&Key::Named(winit::keyboard::NamedKey::Space)) {
+        if controls.is_held(Action::Jump) {
             move_dir += self.up; // Подъём вверх
         }
-        if self.pressed_keys.contains(&Key::Named(winit::keyboard::NamedKey::Control)) {
+        if controls.is_held(Action::Crouch) {
             move_dir -= self.up; // Спуск вниз
         }
-        if self.pressed_keys.contains(&Key::Character("r".into())) {
-            self.reset(); // Сброс положения камеры
+        if self.mode == CameraMode::Orbit {
+            self.velocity = Vector3::zeros();
+            return; // В орбите камера движется только мышью
         }
 
+        let mut target_velocity = Vector3::zeros();
         if move_dir.norm() > 0.0 {
-            self.position += move_dir.normalize() * self.speed;
+            target_velocity = move_dir.normalize() * self.speed;
+            if controls.is_held(Action::Sprint) {
+                target_velocity *= SPRINT_MULTIPLIER;
+            }
         }
+        // Экспоненциальное сглаживание не зависит от частоты кадров
+        self.velocity += (target_velocity - self.velocity) * (1.0 - (-ACCELERATION * dt).exp());
+        self.position += self.velocity * dt;
     }
 
-    pub fn process_mouse(&mut self, cursor_x: f32, cursor_y: f32) {
-        let delta_x = cursor_x - self.last_cursor_x;
-        let delta_y = self.last_cursor_y - cursor_y; // Инверсия Y
-
-        self.last_cursor_x = cursor_x;
-        self.last_cursor_y = cursor_y;
+    /// Сырое смещение мыши (y вниз): поворот взгляда или перетаскивание орбиты
+    pub fn process_mouse(&mut self, delta_x: f32, delta_y: f32) {
+        if self.mode == CameraMode::Orbit {
+            self.orbit.drag_by(delta_x, delta_y);
+            return;
+        }
+        if self.projection == Projection::Isometric {
+            return;
+        }
 
+        let delta_y = if self.invert_y { delta_y } else { -delta_y };
         self.yaw += delta_x * self.sensitivity;
         self.pitch += delta_y * self.sensitivity;
+        self.update_direction();
+    }
 
-        self.pitch = self.pitch.clamp(-89.0, 89.0);
+    /// Направление взгляда по углам yaw/pitch
+    pub fn update_direction(&mut self) {
+        self.pitch = self.pitch.clamp(-90.0, 90.0);
 
         let yaw_radians = self.yaw.to_radians();
         let pitch_radians = self.pitch.to_radians();
@@ -100,15 +191,346 @@ impl Camera {
         .normalize();
     }
 
+    /// Луч выбора через центр экрана
+    pub fn pick_ray(&self) -> (Point3<f32>, Vector3<f32>) {
+        self.ray_through(0.0, 0.0)
+    }
+
+    /// Луч через точку экрана в NDC (x вправо, y вверх, от -1 до 1).
+    /// В перспективе лучи расходятся из камеры, в ортографии параллельны взгляду.
+    pub fn ray_through(&self, ndc_x: f32, ndc_y: f32) -> (Point3<f32>, Vector3<f32>) {
+        let inverse = self.view_proj_matrix().try_inverse().unwrap_or_else(Matrix4::identity);
+        let unproject = |z: f32| {
+            let p = inverse * Vector4::new(ndc_x, ndc_y, z, 1.0);
+            Point3::from(p.xyz() / p.w)
+        };
+        let (near, far) = (unproject(0.0), unproject(1.0));
+        match self.projection {
+            // Из самой камеры, чтобы выбирать и то, что ближе плоскости отсечения
+            Projection::Perspective => (self.position, (far - self.position).normalize()),
+            Projection::Orthographic | Projection::Isometric => (near, (far - near).normalize()),
+        }
+    }
+
     pub fn reset(&mut self) {
         self.position = Point3::new(0.0, 0.0, 3.0);
         self.yaw = -90.0;
         self.pitch = 0.0;
         self.direction = Vector3::new(0.0, 0.0, -1.0);
+        self.velocity = Vector3::zeros();
+        self.projection = Projection::Perspective;
+        self.orbit.stop();
+        self.orbit.target = self.position + self.direction * self.orbit.distance;
+    }
+
+    /// Переключает проекцию: перспектива → ортография → изометрия → перспектива.
+    /// `focus` – расстояние до точки, масштаб вокруг которой сохраняется.
+    pub fn cycle_projection(&mut self, focus: f32) {
+        let projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Isometric,
+            Projection::Isometric => Projection::Perspective,
+        };
+        self.set_projection(projection, focus);
+    }
+
+    pub fn set_projection(&mut self, projection: Projection, focus: f32) {
+        if self.projection == Projection::Perspective && projection != Projection::Perspective {
+            // В плоскости фокуса предметы остаются того же размера
+            self.ortho_height = (focus * (self.fov * 0.5).tan()).max(MIN_ORTHO_HEIGHT);
+        }
+        self.projection = projection;
+        if projection == Projection::Isometric {
+            // Ближайший из четырёх изометрических ракурсов
+            let yaw = ((self.yaw - 45.0) / 90.0).round() * 90.0 + 45.0;
+            self.look_around_focus(yaw, ISOMETRIC_PITCH, focus);
+        }
+    }
+
+    /// Ортографический вид вдоль оси вокруг точки фокуса
+    pub fn set_axis_view(&mut self, view: AxisView, focus: f32) {
+        if self.projection == Projection::Perspective {
+            self.set_projection(Projection::Orthographic, focus);
+        }
+        self.projection = Projection::Orthographic;
+        let (yaw, pitch) = match view {
+            AxisView::Top => (-90.0, -90.0),
+            AxisView::Front => (-90.0, 0.0),
+            AxisView::Side => (180.0, 0.0),
+        };
+        self.look_around_focus(yaw, pitch, focus);
+    }
+
+    /// Поворачивает взгляд, оставляя точку фокуса в центре экрана
+    fn look_around_focus(&mut self, yaw: f32, pitch: f32, focus: f32) {
+        let focus = focus.max(MIN_DISTANCE);
+        let target = self.position + self.direction * focus;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.update_direction();
+        self.position = target - self.direction * focus;
+        self.velocity = Vector3::zeros();
+        self.orbit.stop();
+        self.orbit.distance = focus;
+        self.orbit.target = target;
+    }
+
+    /// Переключает свободный полёт и орбиту, не сдвигая вид.
+    /// `focus` – расстояние до точки, вокруг которой начнётся вращение.
+    pub fn toggle_mode(&mut self, focus: f32) {
+        self.mode = match self.mode {
+            CameraMode::FreeFly => {
+                self.orbit.distance = focus.max(MIN_DISTANCE);
+                self.orbit.target = self.position + self.direction * self.orbit.distance;
+                CameraMode::Orbit
+            }
+            CameraMode::Orbit => CameraMode::FreeFly,
+        };
+        self.orbit.stop();
+        self.orbit.drag = None;
+    }
+
+    /// Продвигает сглаженное движение орбиты на `dt` секунд
+    pub fn update_orbit(&mut self, dt: f32) {
+        if self.mode != CameraMode::Orbit {
+            return;
+        }
+
+        let step = self.orbit.step(dt);
+        if self.projection != Projection::Isometric {
+            self.yaw += step.yaw;
+            self.pitch += step.pitch;
+            self.update_direction();
+        }
+
+        let right = self.right();
+        let up = right.cross(&self.direction);
+        let orbit = &mut self.orbit;
+        orbit.distance = (orbit.distance * (-step.zoom).exp()).clamp(MIN_DISTANCE, self.far * 0.5);
+
+        // Тянем сцену за курсором: цель уходит в противоположную сторону.
+        // В ортографии размер вида задаёт масштаб, а не расстояние.
+        let scale = match self.projection {
+            Projection::Perspective => orbit.distance,
+            Projection::Orthographic | Projection::Isometric => self.ortho_height / (self.fov * 0.5).tan(),
+        };
+        orbit.target += (up * step.pan.y - right * step.pan.x) * scale;
+
+        self.position = orbit.target - self.direction * orbit.distance;
+    }
+
+    /// Прокрутка колеса: в ортографии – масштаб, в орбите – приближение к цели, в полёте – скорость
+    pub fn process_scroll(&mut self, lines: f32) {
+        if self.projection != Projection::Perspective {
+            // Расстояние до цели в ортографии не меняет размер, поэтому меняем сам масштаб
+            self.ortho_height = (self.ortho_height / ORTHO_ZOOM_STEP.powf(lines)).clamp(MIN_ORTHO_HEIGHT, self.far * 0.5);
+            return;
+        }
+        match self.mode {
+            CameraMode::Orbit => self.orbit.zoom_by(lines),
+            CameraMode::FreeFly => {
+                self.speed = (self.speed * SPEED_STEP.powf(lines)).clamp(MIN_SPEED, MAX_SPEED);
+            }
+        }
+    }
+
+    /// Отодвигает камеру вдоль взгляда так, чтобы `bounds` целиком попал в кадр
+    pub fn frame_bounds(&mut self, bounds: &Aabb) {
+        let half_vertical = self.fov * 0.5;
+        let half_horizontal = (half_vertical.tan() * self.aspect_ratio).atan();
+        let mut distance = bounds.radius() / half_vertical.min(half_horizontal).sin();
+        if self.projection != Projection::Perspective {
+            // Размер задаёт масштаб, а камера лишь не должна резать сцену ближней плоскостью
+            self.ortho_height = bounds.radius() / self.aspect_ratio.min(1.0);
+            distance = bounds.radius() + self.near * 2.0;
+        }
+
+        self.orbit.stop();
+        self.orbit.target = bounds.center();
+        self.orbit.distance = distance.max(MIN_DISTANCE);
+        self.position = self.orbit.target - self.direction * self.orbit.distance;
     }
 
 
     pub fn view_proj_matrix(&self) -> Matrix4<f32> {
         self.projection_matrix() * self.view_matrix()
     }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_proj_matrix())
+    }
+}
+
+wgsl_struct! {
+    /// Uniform камеры для вершинного шейдера (`Camera` в WGSL)
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct CameraUniform as "Camera" {
+        pub view_proj: [[f32; 4]; 4],
+        /// dot(xyz, p) + w – расстояние от камеры до точки `p` вдоль взгляда, в любой проекции
+        /// (w после ортографической проекции всегда 1, им глубину не получить)
+        pub view_depth: [f32; 4],
+    }
+}
+
+impl CameraUniform {
+    pub fn new(camera: &Camera) -> Self {
+        let direction = camera.direction;
+        Self {
+            view_proj: camera.view_proj_matrix().into(),
+            view_depth: [direction.x, direction.y, direction.z, -direction.dot(&camera.position.coords)],
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    fn bounds() -> Aabb {
+        Aabb { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) }
+    }
+
+    #[test]
+    fn toggling_mode_keeps_the_view() {
+        let mut camera = Camera::new(1.5);
+        camera.position = Point3::new(0.5, 2.0, 4.0);
+        camera.yaw = -110.0;
+        camera.pitch = -20.0;
+        camera.update_direction();
+        let view = camera.view_matrix();
+
+        camera.toggle_mode(3.0);
+        camera.update_orbit(FRAME);
+        assert!((camera.view_matrix() - view).norm() < 1e-4);
+
+        camera.toggle_mode(3.0);
+        camera.update_orbit(FRAME);
+        assert!((camera.view_matrix() - view).norm() < 1e-4);
+    }
+
+    #[test]
+    fn orbit_rotates_around_target_and_settles() {
+        let mut camera = Camera::new(1.0);
+        camera.toggle_mode(3.0);
+        let target = camera.orbit.target;
+        camera.orbit.drag = Some(crate::renderer::orbit::OrbitDrag::Rotate);
+        camera.orbit.drag_by(100.0, 0.0);
+        for _ in 0..200 {
+            camera.update_orbit(FRAME);
+        }
+        assert!(((camera.position - target).norm() - 3.0).abs() < 1e-4);
+        assert!((camera.yaw - (-90.0 + 30.0)).abs() < 1e-2);
+        assert!(camera.orbit.pending_yaw.abs() < 1e-6);
+    }
+
+    /// Пролетает одну секунду с нажатой W кадрами длиной `dt`
+    fn fly_one_second(dt: f32) -> Point3<f32> {
+        let mut camera = Camera::new(1.0);
+        let mut controls = Controls::default();
+        controls.press(winit::keyboard::KeyCode::KeyW);
+        for _ in 0..(1.0 / dt).round() as usize {
+            camera.process_keyboard(&controls, dt);
+        }
+        camera.position
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_frame_rate() {
+        let slow = fly_one_second(1.0 / 30.0);
+        let fast = fly_one_second(1.0 / 240.0);
+        // Разгон даёт небольшую разницу дискретизации, но не в разы, как раньше
+        assert!((slow - fast).norm() < 0.05);
+        assert!((Point3::new(0.0, 0.0, 3.0) - fast).norm() > 1.0);
+    }
+
+    #[test]
+    fn orbit_damping_does_not_depend_on_frame_rate() {
+        let settle = |dt: f32| {
+            let mut orbit = Orbit { pending_yaw: 30.0, ..Default::default() };
+            (0..(0.1 / dt).round() as usize).map(|_| orbit.step(dt).yaw).sum::<f32>()
+        };
+        assert!((settle(1.0 / 30.0) - settle(1.0 / 240.0)).abs() < 1e-3);
+    }
+
+    fn assert_bounds_visible(camera: &Camera) {
+        let frustum = camera.frustum();
+        for x in [-1.0, 1.0] {
+            for y in [-1.0, 1.0] {
+                for z in [-1.0, 1.0] {
+                    let corner = Point3::new(x, y, z);
+                    assert!(frustum.planes.iter().all(|p| p.distance(&corner) >= -1e-4));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn framed_bounds_are_inside_the_frustum() {
+        for projection in [Projection::Perspective, Projection::Orthographic, Projection::Isometric] {
+            let mut camera = Camera::new(0.5);
+            camera.set_projection(projection, 3.0);
+            camera.frame_bounds(&bounds());
+            assert_bounds_visible(&camera);
+        }
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let mut camera = Camera::new(1.5);
+        camera.set_projection(Projection::Orthographic, 3.0);
+        let (center, center_dir) = camera.ray_through(0.0, 0.0);
+        let (corner, corner_dir) = camera.ray_through(1.0, 1.0);
+        assert!((corner_dir - center_dir).norm() < 1e-4);
+        assert!((center_dir - camera.direction).norm() < 1e-4);
+        // Угол экрана сдвинут на половину ширины и высоты вида
+        let offset = corner - center;
+        assert!((offset.y - camera.ortho_height).abs() < 1e-3);
+        assert!((offset.x - camera.ortho_height * 1.5).abs() < 1e-3);
+        // Масштаб в плоскости фокуса сохранился
+        assert!((camera.ortho_height - 3.0 * (camera.fov * 0.5).tan()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn top_view_looks_straight_down() {
+        let mut camera = Camera::new(1.0);
+        camera.set_axis_view(AxisView::Top, 3.0);
+        assert!((camera.direction - Vector3::new(0.0, -1.0, 0.0)).norm() < 1e-5);
+        assert!((camera.position - Point3::new(0.0, 3.0, 0.0)).norm() < 1e-4);
+        assert!(camera.view_matrix().iter().all(|v| v.is_finite()));
+        assert_bounds_visible(&camera);
+    }
+
+    #[test]
+    fn isometric_view_ignores_mouse_look() {
+        let mut camera = Camera::new(1.0);
+        camera.set_projection(Projection::Isometric, 3.0);
+        assert!((camera.yaw - (-135.0)).abs() < 1e-4);
+        let direction = camera.direction;
+        camera.process_mouse(100.0, 50.0);
+        assert_eq!(camera.direction, direction);
+        // Диагональ куба смотрит прямо на камеру
+        let diagonal = Vector3::new(-1.0, -1.0, -1.0).normalize();
+        assert!((direction - diagonal).norm() < 1e-4);
+
+        let height = camera.ortho_height;
+        camera.process_scroll(1.0);
+        assert!(camera.ortho_height < height);
+    }
+
+    #[test]
+    fn uniform_view_depth_is_distance_along_the_view() {
+        let mut camera = Camera::new(1.0);
+        camera.set_axis_view(AxisView::Front, 5.0);
+        let point = camera.position + camera.direction * 7.0 + camera.right() * 2.0;
+        let uniform = CameraUniform::new(&camera);
+        let [x, y, z, w] = uniform.view_depth;
+        assert!((Vector3::new(x, y, z).dot(&point.coords) + w - 7.0).abs() < 1e-4);
+        // В ортографии w после проекции – не глубина
+        let clip = Matrix4::from(uniform.view_proj) * point.to_homogeneous();
+        assert_eq!(clip.w, 1.0);
+    }
 }
@@ -0,0 +1,288 @@
+use std::path::{Path, PathBuf};
+
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::renderer::camera::{Camera, CameraMode};
+
+/// Положение и ориентация камеры
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub position: [f32; 3],
+    pub yaw: f32,   // Градусы
+    pub pitch: f32, // Градусы
+    pub fov: f32,   // Вертикальный угол обзора в градусах
+}
+
+impl Pose {
+    pub fn capture(camera: &Camera) -> Self {
+        Self {
+            position: camera.position.into(),
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            fov: camera.fov.to_degrees(),
+        }
+    }
+
+    /// Ставит камеру в эту позу (в режиме свободного полёта, без инерции)
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.mode = CameraMode::FreeFly;
+        camera.position = Point3::from(self.position);
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        camera.fov = self.fov.to_radians();
+        camera.velocity = Vector3::zeros();
+        camera.update_direction();
+        camera.orbit.stop();
+        camera.orbit.target = camera.position + camera.direction * camera.orbit.distance;
+    }
+
+    /// Поворот, переводящий +Z во взгляд камеры, а +X – в `-Camera::right()`. Собирается из углов,
+    /// а не через `face_towards`, поэтому определён и при взгляде строго вверх или вниз.
+    fn orientation(&self) -> UnitQuaternion<f32> {
+        let yaw = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), (90.0 - self.yaw).to_radians());
+        yaw * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -self.pitch.to_radians())
+    }
+
+    /// Углы по повороту из `orientation`; yaw берётся по горизонтальной оси, а не по взгляду,
+    /// чтобы не теряться при pitch ±90
+    fn angles(orientation: &UnitQuaternion<f32>) -> (f32, f32) {
+        let direction = orientation * Vector3::z();
+        let side = orientation * Vector3::x();
+        let horizontal = direction.x.hypot(direction.z); // atan2 точнее asin у ±90
+        (side.x.atan2(-side.z).to_degrees(), direction.y.atan2(horizontal).to_degrees())
+    }
+}
+
+/// Именованная поза
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub pose: Pose,
+}
+
+/// Поза в момент времени `time` (секунды от начала пути)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f32,
+    pub pose: Pose,
+}
+
+/// Пролёт по ключевым кадрам: позиция – Catmull-Rom, ориентация – slerp
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub name: String,
+    pub keyframes: Vec<Keyframe>, // По возрастанию времени
+}
+
+/// Сплайн Catmull-Rom между `p1` и `p2`, `t` в [0, 1]
+fn catmull_rom(p0: Vector3<f32>, p1: Vector3<f32>, p2: Vector3<f32>, p3: Vector3<f32>, t: f32) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+impl CameraPath {
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// Поза в момент `time` (за пределами пути – крайние кадры)
+    pub fn sample(&self, time: f32) -> Option<Pose> {
+        let keys = &self.keyframes;
+        let first = keys.first()?;
+        if keys.len() == 1 || time <= first.time {
+            return Some(first.pose);
+        }
+        let Some(i) = keys.windows(2).position(|w| time < w[1].time) else {
+            return keys.last().map(|k| k.pose);
+        };
+
+        // Соседи за краями пути повторяют крайние кадры
+        let at = |j: usize| Vector3::from(keys[j.min(keys.len() - 1)].pose.position);
+        let (a, b) = (&keys[i], &keys[i + 1]);
+        let t = (time - a.time) / (b.time - a.time).max(f32::EPSILON);
+        let position = catmull_rom(at(i.saturating_sub(1)), at(i), at(i + 1), at(i + 2), t);
+
+        let (yaw, pitch) = Pose::angles(&a.pose.orientation().slerp(&b.pose.orientation(), t));
+        Some(Pose {
+            position: position.into(),
+            yaw,
+            pitch,
+            fov: a.pose.fov + (b.pose.fov - a.pose.fov) * t,
+        })
+    }
+}
+
+/// Закладки и пути камеры, сохраняются в TOML
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraPresets {
+    pub bookmarks: Vec<Bookmark>,
+    pub paths: Vec<CameraPath>,
+    /// Файл не прочитался: набор пуст, и `save` его не перезапишет, чтобы не потерять закладки
+    #[serde(skip)]
+    pub load_failed: bool,
+}
+
+impl CameraPresets {
+    /// Файл закладок и путей лежит рядом с файлом настроек: `cuborum.toml` → `cuborum.camera.toml`
+    pub fn file_for(config_file: &Path) -> PathBuf {
+        config_file.with_extension("camera.toml")
+    }
+
+    /// Читает файл; если его нет – пустой набор. Если он испорчен – тоже пустой, но сохранять поверх нельзя.
+    pub fn load(path: &Path) -> Self {
+        let failed = Self { load_failed: true, ..Self::default() };
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                tracing::warn!("Failed to read {}: {}", path.display(), err);
+                return failed;
+            }
+        };
+        toml::from_str(&text).unwrap_or_else(|err| {
+            tracing::warn!("Failed to parse {}: {}", path.display(), err);
+            failed
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if self.load_failed {
+            return Err(std::io::Error::other("the file failed to load, fix it first to avoid losing bookmarks"));
+        }
+        let text = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    pub fn path(&self, name: &str) -> Option<&CameraPath> {
+        self.paths.iter().find(|p| p.name == name)
+    }
+}
+
+/// Проигрывание пути: время идёт шагами симуляции
+#[derive(Clone, Debug)]
+pub struct PathPlayback {
+    pub path: CameraPath,
+    pub time: f32,
+}
+
+impl PathPlayback {
+    pub fn new(path: CameraPath) -> Self {
+        Self { path, time: 0.0 }
+    }
+
+    /// Ставит камеру в позу текущего момента и сдвигает время. Возвращает false, когда путь закончился.
+    pub fn advance(&mut self, camera: &mut Camera, dt: f32) -> bool {
+        if let Some(pose) = self.path.sample(self.time) {
+            pose.apply(camera);
+        }
+        self.time += dt;
+        self.time <= self.path.duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(x: f32, yaw: f32) -> Pose {
+        Pose { position: [x, 0.0, 0.0], yaw, pitch: 0.0, fov: 45.0 }
+    }
+
+    fn path() -> CameraPath {
+        CameraPath {
+            name: "demo".to_string(),
+            keyframes: vec![
+                Keyframe { time: 0.0, pose: pose(0.0, 0.0) },
+                Keyframe { time: 1.0, pose: pose(1.0, 90.0) },
+                Keyframe { time: 3.0, pose: pose(3.0, 90.0) },
+            ],
+        }
+    }
+
+    #[test]
+    fn path_passes_through_keyframes() {
+        let path = path();
+        for key in &path.keyframes {
+            let sampled = path.sample(key.time).unwrap();
+            assert!((Vector3::from(sampled.position) - Vector3::from(key.pose.position)).norm() < 1e-5);
+            assert!((sampled.yaw - key.pose.yaw).abs() < 1e-3);
+        }
+        assert_eq!(path.sample(10.0), Some(path.keyframes[2].pose));
+    }
+
+    #[test]
+    fn orientation_is_slerped() {
+        let half = path().sample(0.5).unwrap();
+        assert!((half.yaw - 45.0).abs() < 1e-3);
+        assert!(half.pitch.abs() < 1e-3);
+    }
+
+    #[test]
+    fn straight_down_pose_survives_interpolation() {
+        let down = |yaw| Pose { pitch: -90.0, ..pose(0.0, yaw) };
+        let path = CameraPath {
+            name: "top".to_string(),
+            keyframes: vec![Keyframe { time: 0.0, pose: down(-90.0) }, Keyframe { time: 1.0, pose: down(-90.0) }],
+        };
+        let sampled = path.sample(0.5).unwrap();
+        assert!((sampled.pitch + 90.0).abs() < 1e-2 && (sampled.yaw + 90.0).abs() < 1e-2);
+
+        let turning = CameraPath {
+            keyframes: vec![Keyframe { time: 0.0, pose: down(-90.0) }, Keyframe { time: 1.0, pose: down(0.0) }],
+            ..path
+        };
+        let half = turning.sample(0.5).unwrap();
+        assert!((half.pitch + 90.0).abs() < 1e-2 && (half.yaw + 45.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn catmull_rom_keeps_collinear_points_on_the_line() {
+        let mid = path().sample(2.0).unwrap();
+        assert!(mid.position[1].abs() < 1e-6 && mid.position[2].abs() < 1e-6);
+        assert!(mid.position[0] > 1.0 && mid.position[0] < 3.0);
+    }
+
+    #[test]
+    fn pose_round_trips_through_camera() {
+        let mut camera = Camera::new(1.0);
+        let original = Pose { position: [0.5, 1.0, -2.0], yaw: 30.0, pitch: -10.0, fov: 60.0 };
+        original.apply(&mut camera);
+        let captured = Pose::capture(&camera);
+        assert!((captured.fov - original.fov).abs() < 1e-4);
+        assert_eq!(captured.position, original.position);
+    }
+
+    #[test]
+    fn presets_round_trip_through_toml() {
+        let presets = CameraPresets {
+            bookmarks: vec![Bookmark { name: "start".to_string(), pose: pose(1.0, 0.0) }],
+            paths: vec![path()],
+            ..Default::default()
+        };
+        let text = toml::to_string_pretty(&presets).unwrap();
+        assert_eq!(toml::from_str::<CameraPresets>(&text).unwrap(), presets);
+    }
+
+    #[test]
+    fn presets_file_sits_next_to_the_config() {
+        assert_eq!(CameraPresets::file_for(Path::new("cuborum.toml")), Path::new("cuborum.camera.toml"));
+        assert_eq!(CameraPresets::file_for(Path::new("worlds/castle.toml")), Path::new("worlds/castle.camera.toml"));
+    }
+
+    #[test]
+    fn broken_file_is_not_overwritten() {
+        let path = std::env::temp_dir().join(format!("cuborum-camera-{}.toml", std::process::id()));
+        std::fs::write(&path, "bookmarks = [").unwrap();
+        let mut presets = CameraPresets::load(&path);
+        presets.bookmarks.push(Bookmark { name: "new".to_string(), pose: pose(0.0, 0.0) });
+        let saved = presets.save(&path);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(saved.is_err());
+        assert_eq!(text, "bookmarks = [");
+    }
+}
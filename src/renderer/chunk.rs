@@ -0,0 +1,310 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra::Point3;
+use wgpu::util::DeviceExt;
+use wgpu::{Buffer, Device, Queue};
+
+use crate::renderer::frustum::Aabb;
+use crate::renderer::lod::{chunk_lod, lod_factor, LodGrids, LodSettings, LOD_COUNT};
+use crate::renderer::mesh::{build_region_mesh, Mesh, MeshOptions};
+use crate::renderer::vertex::{create_index_buffer, create_vertex_buffer, Vertex};
+use crate::renderer::voxel::VoxelGrid;
+
+/// Размер чанка в вокселях по каждой оси
+pub const CHUNK_SIZE: usize = 8;
+/// Запас граней в участке буферов каждого меша, чтобы правки вокселей обычно влезали на место
+const SLOT_SLACK_FACES: usize = 16;
+
+/// CPU-меши одного чанка, по одному на уровень детализации
+pub struct ChunkMesh {
+    pub coords: [usize; 3], // Координаты чанка (в чанках)
+    pub bounds: Aabb,       // Границы в мировых координатах
+    pub lods: Vec<Mesh>,
+}
+
+/// Число чанков по каждой оси
+pub fn chunk_count(grid: &VoxelGrid) -> usize {
+    grid.size.div_ceil(CHUNK_SIZE)
+}
+
+/// Диапазон ячеек чанка `[min, max)`
+pub fn chunk_range(grid: &VoxelGrid, coords: [usize; 3]) -> ([usize; 3], [usize; 3]) {
+    let min = coords.map(|c| c * CHUNK_SIZE);
+    let max = min.map(|c| (c + CHUNK_SIZE).min(grid.size));
+    (min, max)
+}
+
+/// Индекс чанка в списке `build_chunks`
+pub fn chunk_index(grid: &VoxelGrid, [x, y, z]: [usize; 3]) -> usize {
+    let count = chunk_count(grid);
+    (z * count + y) * count + x
+}
+
+/// Чанки, чей меш уровня `level` зависит от вокселя `cell`: в них лежат его грубая ячейка
+/// и соседние с ней (грани и AO)
+pub fn affected_chunks(grid: &VoxelGrid, cell: [usize; 3], level: usize) -> impl Iterator<Item = [usize; 3]> {
+    let factor = lod_factor(level);
+    let last = chunk_count(grid) - 1;
+    let min = cell.map(|c| (c / factor).saturating_sub(1) * factor / CHUNK_SIZE);
+    let max = cell.map(|c| (((c / factor + 2) * factor - 1) / CHUNK_SIZE).min(last));
+    (min[2]..=max[2]).flat_map(move |z| (min[1]..=max[1]).flat_map(move |y| (min[0]..=max[0]).map(move |x| [x, y, z])))
+}
+
+/// Мировые границы чанка
+pub fn chunk_bounds(grid: &VoxelGrid, coords: [usize; 3]) -> Aabb {
+    let (min, max) = chunk_range(grid, coords);
+    let origin = grid.origin();
+    let voxel_size = grid.voxel_size();
+    let world = |c: [usize; 3]| {
+        Point3::new(
+            origin[0] + c[0] as f32 * voxel_size,
+            origin[1] + c[1] as f32 * voxel_size,
+            origin[2] + c[2] as f32 * voxel_size,
+        )
+    };
+    Aabb { min: world(min), max: world(max) }
+}
+
+impl ChunkMesh {
+    pub fn build(grid: &VoxelGrid, lod_grids: &LodGrids, options: MeshOptions, coords: [usize; 3]) -> Self {
+        Self {
+            coords,
+            bounds: chunk_bounds(grid, coords),
+            lods: (0..LOD_COUNT).map(|level| build_lod(grid, lod_grids, options, coords, level)).collect(),
+        }
+    }
+
+    /// Перестраивает меш одного уровня
+    pub fn rebuild_lod(&mut self, grid: &VoxelGrid, lod_grids: &LodGrids, options: MeshOptions, level: usize) {
+        self.lods[level] = build_lod(grid, lod_grids, options, self.coords, level);
+    }
+}
+
+fn build_lod(grid: &VoxelGrid, lod_grids: &LodGrids, options: MeshOptions, coords: [usize; 3], level: usize) -> Mesh {
+    // Тот же участок пространства в ячейках грубой сетки
+    let (min, max) = chunk_range(grid, coords);
+    let factor = lod_factor(level);
+    let lod_grid = lod_grids.grid(grid, level);
+    let lod_min = min.map(|c| c / factor);
+    let lod_max = max.map(|c| c.div_ceil(factor).min(lod_grid.size));
+    build_region_mesh(lod_grid, options, lod_min, lod_max)
+}
+
+/// Строит меши всех чанков сетки
+pub fn build_chunks(grid: &VoxelGrid, lod_grids: &LodGrids, options: MeshOptions) -> Vec<ChunkMesh> {
+    let count = chunk_count(grid);
+    let mut chunks = Vec::with_capacity(count * count * count);
+    for z in 0..count {
+        for y in 0..count {
+            for x in 0..count {
+                chunks.push(ChunkMesh::build(grid, lod_grids, options, [x, y, z]));
+            }
+        }
+    }
+    chunks
+}
+
+/// Диапазон одного уровня детализации чанка внутри общих буферов
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkDraw {
+    pub bounds: Aabb,
+    pub lod: usize,
+    pub first_index: u32,
+    pub index_count: u32,
+    pub base_vertex: i32,
+    pub index_capacity: u32,  // Размер участка в буферах, меш до этого размера заменяется на месте
+    pub vertex_capacity: u32,
+}
+
+/// Описание чанка для compute-отсечения (совпадает с `ChunkInfo` в cull.wgsl)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ChunkInfo {
+    pub aabb_min: [f32; 3],
+    pub first_index: u32,
+    pub aabb_max: [f32; 3],
+    pub index_count: u32,
+    pub base_vertex: i32,
+    pub lod: u32,
+    pub _padding: [u32; 2],
+}
+
+impl ChunkInfo {
+    pub fn new(draw: &ChunkDraw) -> Self {
+        Self {
+            aabb_min: draw.bounds.min.into(),
+            first_index: draw.first_index,
+            aabb_max: draw.bounds.max.into(),
+            index_count: draw.index_count,
+            base_vertex: draw.base_vertex,
+            lod: draw.lod as u32,
+            _padding: [0; 2],
+        }
+    }
+}
+
+/// Аргументы `draw_indexed_indirect` (раскладка задана wgpu)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+/// Все чанки в одном вершинном и одном индексном буфере, чтобы их можно было
+/// нарисовать одним `multi_draw_indexed_indirect`
+pub struct ChunkBatch {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub draws: Vec<ChunkDraw>,
+    pub info_buffer: Buffer,     // ChunkInfo на каждый чанк (вход compute-отсечения)
+    pub indirect_buffer: Buffer, // DrawIndexedIndirectArgs на каждый чанк (выход)
+}
+
+impl ChunkBatch {
+    pub fn new(device: &Device, chunks: &[ChunkMesh]) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut draws = Vec::with_capacity(chunks.len());
+
+        for chunk in chunks {
+            for (lod, mesh) in chunk.lods.iter().enumerate() {
+                // Грань – 4 вершины и 6 индексов; участок берём с запасом
+                let faces = mesh.indices.len() / 6;
+                let capacity = faces + faces / 4 + SLOT_SLACK_FACES;
+                draws.push(ChunkDraw {
+                    bounds: chunk.bounds,
+                    lod,
+                    first_index: indices.len() as u32,
+                    index_count: mesh.indices.len() as u32,
+                    base_vertex: vertices.len() as i32,
+                    index_capacity: (capacity * 6) as u32,
+                    vertex_capacity: (capacity * 4) as u32,
+                });
+                vertices.extend_from_slice(&mesh.vertices);
+                vertices.resize(vertices.len() + capacity * 4 - mesh.vertices.len(), Vertex::zeroed());
+                indices.extend_from_slice(&mesh.indices);
+                indices.resize(indices.len() + capacity * 6 - mesh.indices.len(), 0);
+            }
+        }
+
+        let mut infos: Vec<ChunkInfo> = draws.iter().map(ChunkInfo::new).collect();
+        // Пустой storage-буфер привязать нельзя
+        if infos.is_empty() {
+            infos.push(ChunkInfo::zeroed());
+        }
+
+        let info_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk Info Buffer"),
+            contents: bytemuck::cast_slice(&infos),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let indirect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Indirect Buffer"),
+            size: (infos.len() * std::mem::size_of::<DrawIndexedIndirectArgs>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_SRC, // Копия – для CullStats
+            mapped_at_creation: false,
+        });
+
+        Self {
+            vertex_buffer: create_vertex_buffer(device, &vertices),
+            index_buffer: create_index_buffer(device, &indices),
+            draws,
+            info_buffer,
+            indirect_buffer,
+        }
+    }
+
+    /// Заменяет меш уровня `lod` чанка с индексом `chunk` на месте, без пересоздания буферов.
+    /// false – меш не влез в свой участок, тогда батч нужно собрать заново.
+    pub fn update(&mut self, queue: &Queue, chunk: usize, lod: usize, mesh: &Mesh) -> bool {
+        let index = chunk * LOD_COUNT + lod;
+        let draw = &mut self.draws[index];
+        if mesh.vertices.len() > draw.vertex_capacity as usize || mesh.indices.len() > draw.index_capacity as usize {
+            return false;
+        }
+        if !mesh.indices.is_empty() {
+            let vertex_offset = draw.base_vertex as usize * std::mem::size_of::<Vertex>();
+            let index_offset = draw.first_index as usize * std::mem::size_of::<u32>();
+            queue.write_buffer(&self.vertex_buffer, vertex_offset as wgpu::BufferAddress, bytemuck::cast_slice(&mesh.vertices));
+            queue.write_buffer(&self.index_buffer, index_offset as wgpu::BufferAddress, bytemuck::cast_slice(&mesh.indices));
+        }
+        draw.index_count = mesh.indices.len() as u32;
+        let info_offset = index * std::mem::size_of::<ChunkInfo>();
+        queue.write_buffer(&self.info_buffer, info_offset as wgpu::BufferAddress, bytemuck::bytes_of(&ChunkInfo::new(draw)));
+        true
+    }
+
+    /// Есть ли что рисовать (пустые буферы нельзя привязывать как вершинные)
+    pub fn is_empty(&self) -> bool {
+        self.draws.iter().all(|d| d.index_count == 0)
+    }
+
+    /// Привязывает общие буферы к проходу
+    pub fn bind<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    }
+
+    /// Диапазоны, которые нужно рисовать для текущей позиции камеры (по одному уровню на чанк)
+    pub fn selected<'a>(
+        &'a self,
+        settings: &'a LodSettings,
+        camera_position: &'a Point3<f32>,
+    ) -> impl Iterator<Item = &'a ChunkDraw> + 'a {
+        self.draws
+            .iter()
+            .filter(move |d| d.index_count > 0 && d.lod == chunk_lod(settings, &d.bounds, camera_position))
+    }
+
+    /// Рисует один чанк из общих буферов
+    pub fn draw(pass: &mut wgpu::RenderPass<'_>, draw: &ChunkDraw) {
+        pass.draw_indexed(draw.first_index..draw.first_index + draw.index_count, draw.base_vertex, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::lod::LodColor;
+    use crate::renderer::voxel::{Voxel, VOXEL_SOLID};
+
+    #[test]
+    fn affected_chunks_cover_neighbours_of_the_coarse_cell() {
+        let grid = VoxelGrid::new(32);
+        assert_eq!(affected_chunks(&grid, [12, 12, 12], 0).collect::<Vec<_>>(), [[1, 1, 1]]);
+        assert_eq!(affected_chunks(&grid, [8, 12, 12], 0).count(), 2);
+        // На уровне 3 грубая ячейка – целый чанк, соседние ячейки – соседние чанки
+        assert_eq!(affected_chunks(&grid, [12, 12, 12], 3).count(), 27);
+        assert_eq!(affected_chunks(&grid, [0, 0, 0], 3).count(), 8);
+    }
+
+    #[test]
+    fn rebuilding_affected_chunks_matches_full_build() {
+        let mut grid = VoxelGrid::new(32);
+        grid.fill_with_test_pattern();
+        // Без запечатывания границ от соседа зависят и грани на краю чанка
+        let options = MeshOptions::default();
+        let mut lod_grids = LodGrids::new(&grid, LodColor::Average);
+        let mut chunks = build_chunks(&grid, &lod_grids, options);
+
+        for cell in [[8, 16, 8], [9, 16, 8], [8, 17, 8], [9, 17, 8], [23, 15, 0]] {
+            let voxel = if cell[1] < 16 { Voxel::empty() } else { Voxel::new(VOXEL_SOLID, 0, 255, 0, 255) };
+            grid.set(cell[0], cell[1], cell[2], voxel);
+            lod_grids.update(&grid, cell, LodColor::Average);
+            for level in 0..LOD_COUNT {
+                for coords in affected_chunks(&grid, cell, level) {
+                    chunks[chunk_index(&grid, coords)].rebuild_lod(&grid, &lod_grids, options, level);
+                }
+            }
+        }
+
+        let sizes = |chunks: &[ChunkMesh]| -> Vec<(usize, usize)> {
+            chunks.iter().flat_map(|c| c.lods.iter().map(|m| (m.vertices.len(), m.indices.len()))).collect()
+        };
+        assert_eq!(sizes(&chunks), sizes(&build_chunks(&grid, &lod_grids, options)));
+    }
+}
@@ -0,0 +1,243 @@
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+use nalgebra::Point3;
+
+use crate::renderer::camera::Camera;
+use crate::renderer::chunk::{ChunkBatch, DrawIndexedIndirectArgs};
+use crate::renderer::lod::{chunk_lod, LodSettings};
+use crate::renderer::frustum::Frustum;
+use crate::error::Result;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_cull_pipeline, layout};
+
+/// Размер рабочей группы в cull.wgsl
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Uniform для compute-отсечения: плоскости пирамиды, число диапазонов и выбор LOD
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CullUniform {
+    pub planes: [[f32; 4]; 6],
+    pub camera_position: [f32; 3],
+    pub draw_count: u32,
+    pub lod_distances: [f32; 4], // Пороги уровней 1..3, w не используется
+}
+
+impl CullUniform {
+    pub fn new(frustum: &Frustum, draw_count: u32, camera_position: &Point3<f32>, lod: &LodSettings) -> Self {
+        // Выключенный LOD – пороги в бесконечности, всегда уровень 0
+        let [d1, d2, d3] = if lod.enabled { lod.distances } else { [f32::MAX; 3] };
+        Self {
+            planes: frustum.planes.map(|p| [p.normal.x, p.normal.y, p.normal.z, p.d]),
+            camera_position: (*camera_position).into(),
+            draw_count,
+            lod_distances: [d1, d2, d3, f32::MAX],
+        }
+    }
+}
+
+/// Отсечение чанков на GPU с записью аргументов для `multi_draw_indexed_indirect`.
+/// Создаётся, только если адаптер поддерживает `MULTI_DRAW_INDIRECT`.
+pub struct GpuCulling {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline: ComputePipeline,
+    pub uniform_buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub stats: Option<CullStats>, // Только в отладочной сборке
+}
+
+impl GpuCulling {
+    /// Раскладка: uniform отсечения, описания чанков, аргументы отрисовки
+    pub fn layout_entries() -> [wgpu::BindGroupLayoutEntry; 3] {
+        let compute = wgpu::ShaderStages::COMPUTE;
+        [
+            layout::uniform(0, compute),
+            layout::storage(1, compute, true),  // ChunkInfo
+            layout::storage(2, compute, false), // DrawIndexedIndirectArgs
+        ]
+    }
+
+    pub fn new(device: &Device, pipelines: &mut PipelineCache, batch: &ChunkBatch) -> Result<Self> {
+        let bind_group_layout = layout::bind_group_layout(device, "Cull Bind Group Layout", &Self::layout_entries());
+        let pipeline = create_cull_pipeline(pipelines, &bind_group_layout)?;
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cull Uniform Buffer"),
+            size: std::mem::size_of::<CullUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &uniform_buffer, batch);
+
+        Ok(Self {
+            bind_group_layout,
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            stats: CullStats::new(device, batch),
+        })
+    }
+
+    fn create_bind_group(device: &Device, layout: &BindGroupLayout, uniform_buffer: &Buffer, batch: &ChunkBatch) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Cull Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: batch.info_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: batch.indirect_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Перепривязывает буферы после пересборки батча
+    pub fn rebind(&mut self, device: &Device, batch: &ChunkBatch) {
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.uniform_buffer, batch);
+        self.stats = CullStats::new(device, batch);
+    }
+
+    /// Заполняет indirect-буфер: видимые чанки нужного уровня получают instance_count = 1
+    pub fn run(&self, queue: &Queue, encoder: &mut CommandEncoder, camera: &Camera, lod: &LodSettings, batch: &ChunkBatch) {
+        let draw_count = batch.draws.len() as u32;
+        let uniform = CullUniform::new(&camera.frustum(), draw_count, &camera.position, lod);
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Cull Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(draw_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        drop(compute_pass);
+
+        if let Some(stats) = &self.stats {
+            stats.copy(encoder, batch, lod, &camera.position);
+        }
+    }
+
+    /// Вызывается после отправки кадра: запускает чтение скопированных аргументов
+    pub fn submitted(&self) {
+        if let Some(stats) = &self.stats {
+            stats.map();
+        }
+    }
+}
+
+/// Где сейчас копия indirect-буфера
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Readback {
+    Idle,
+    Copied,  // Копирование записано в энкодер кадра
+    Mapping, // map_async запущен, ждём `mapped`
+}
+
+/// Статистика GPU-отсечения для лога: копия indirect-буфера читается без ожидания,
+/// поэтому числа отстают от кадра на один-два кадра
+pub struct CullStats {
+    buffer: Buffer,
+    selected: RefCell<Vec<bool>>, // Какие диапазоны выбраны по LOD в кадре копии, как у `ChunkBatch::selected`
+    state: Cell<Readback>,
+    mapped: Arc<AtomicBool>,
+}
+
+impl CullStats {
+    /// None в релизной сборке и для пустого батча
+    pub fn new(device: &Device, batch: &ChunkBatch) -> Option<Self> {
+        if !cfg!(debug_assertions) || batch.is_empty() {
+            return None;
+        }
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cull Stats Readback Buffer"),
+            size: batch.indirect_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            buffer,
+            selected: RefCell::new(Vec::new()),
+            state: Cell::new(Readback::Idle),
+            mapped: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Пишет в лог прочитанное в прошлый раз и, если буфер свободен, копирует свежие аргументы
+    fn copy(&self, encoder: &mut CommandEncoder, batch: &ChunkBatch, lod: &LodSettings, camera_position: &Point3<f32>) {
+        if self.state.get() == Readback::Mapping && self.mapped.swap(false, Ordering::Acquire) {
+            let args: Vec<DrawIndexedIndirectArgs> = {
+                let view = self.buffer.slice(..).get_mapped_range();
+                bytemuck::cast_slice(&view).to_vec()
+            };
+            self.buffer.unmap();
+            let (drawn, culled) = count_visibility(&args, &self.selected.borrow());
+            tracing::debug!(drawn, culled, "GPU frustum culling");
+            self.state.set(Readback::Idle);
+        }
+        if self.state.get() == Readback::Idle {
+            encoder.copy_buffer_to_buffer(&batch.indirect_buffer, 0, &self.buffer, 0, self.buffer.size());
+            let selected = batch.draws.iter().map(|draw| draw.lod == chunk_lod(lod, &draw.bounds, camera_position));
+            *self.selected.borrow_mut() = selected.collect();
+            self.state.set(Readback::Copied);
+        }
+    }
+
+    fn map(&self) {
+        if self.state.get() != Readback::Copied {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            if result.is_ok() {
+                mapped.store(true, Ordering::Release);
+            }
+        });
+        self.state.set(Readback::Mapping);
+    }
+}
+
+/// Видимые и отсечённые среди выбранных по LOD непустых диапазонов – те же, что считает отсечение на CPU
+fn count_visibility(args: &[DrawIndexedIndirectArgs], selected: &[bool]) -> (usize, usize) {
+    let candidates = args.iter().zip(selected).filter(|(args, &selected)| selected && args.index_count > 0);
+    candidates.fold((0, 0), |(drawn, culled), (args, _)| {
+        if args.instance_count > 0 {
+            (drawn + 1, culled)
+        } else {
+            (drawn, culled + 1)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(index_count: u32, instance_count: u32) -> DrawIndexedIndirectArgs {
+        DrawIndexedIndirectArgs { index_count, instance_count, first_index: 0, base_vertex: 0, first_instance: 0 }
+    }
+
+    #[test]
+    fn only_selected_non_empty_draws_are_counted() {
+        let args = [
+            args(36, 1), // Видим
+            args(36, 0), // Отсечён
+            args(0, 0),  // Пустой – не считается
+            args(36, 0), // Другой уровень LOD – не считается
+        ];
+        assert_eq!(count_visibility(&args, &[true, true, true, false]), (1, 1));
+    }
+}
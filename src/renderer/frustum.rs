@@ -0,0 +1,154 @@
+use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+
+/// Плоскость `normal · p + d = 0`, нормаль смотрит внутрь пирамиды
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub d: f32,
+}
+
+impl Plane {
+    fn from_row(row: Vector4<f32>) -> Self {
+        let normal = row.xyz();
+        let length = normal.norm();
+        Self {
+            normal: normal / length,
+            d: row.w / length,
+        }
+    }
+
+    /// Знаковое расстояние от точки до плоскости (положительное – внутри)
+    pub fn distance(&self, point: &Point3<f32>) -> f32 {
+        self.normal.dot(&point.coords) + self.d
+    }
+}
+
+/// Ограничивающий параллелепипед, выровненный по осям
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn center(&self) -> Point3<f32> {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    /// Радиус описанной сферы
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).norm() * 0.5
+    }
+}
+
+/// Пирамида видимости: left, right, bottom, top, near, far
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Извлекает плоскости из матрицы view-projection (метод Гриба–Хартманна, z в [0, 1])
+    pub fn from_matrix(m: &Matrix4<f32>) -> Self {
+        let row = |i: usize| m.row(i).transpose();
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        Self {
+            planes: [
+                Plane::from_row(r3 + r0), // left
+                Plane::from_row(r3 - r0), // right
+                Plane::from_row(r3 + r1), // bottom
+                Plane::from_row(r3 - r1), // top
+                Plane::from_row(r2),      // near
+                Plane::from_row(r3 - r2), // far
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, center: &Point3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|p| p.distance(center) >= -radius)
+    }
+
+    /// Быстрый тест по описанной сфере
+    pub fn intersects_aabb_sphere(&self, aabb: &Aabb) -> bool {
+        self.intersects_sphere(&aabb.center(), aabb.radius())
+    }
+
+    /// Консервативный тест: AABB отбрасывается, только если целиком за одной из плоскостей
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // Самая «внутренняя» вершина относительно нормали плоскости
+            let positive = Point3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.distance(&positive) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::camera::Camera;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn near_and_far_planes_of_default_camera() {
+        // Камера в (0, 0, 3) смотрит вдоль -Z
+        let mut camera = Camera::new(1.0);
+        camera.far = 100.0;
+        let frustum = Frustum::from_matrix(&camera.view_proj_matrix());
+        let near = frustum.planes[4];
+        let far = frustum.planes[5];
+
+        assert!(approx(near.normal.z, -1.0));
+        assert!(approx(near.distance(&Point3::new(0.0, 0.0, 3.0 - camera.near)), 0.0));
+        assert!(approx(far.normal.z, 1.0));
+        // Дальняя плоскость хуже обусловлена по точности float
+        assert!(far.distance(&Point3::new(0.0, 0.0, 3.0 - camera.far)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn side_planes_match_field_of_view() {
+        let camera = Camera::new(1.0);
+        let frustum = Frustum::from_matrix(&camera.view_proj_matrix());
+        let half = camera.fov / 2.0;
+
+        // Точка ровно на краю левой плоскости на расстоянии 10 от камеры
+        let edge = Point3::new(-10.0 * half.tan(), 0.0, 3.0 - 10.0);
+        assert!(approx(frustum.planes[0].distance(&edge), 0.0));
+        // Плоскости нормализованы
+        for plane in frustum.planes.iter() {
+            assert!(approx(plane.normal.norm(), 1.0));
+        }
+    }
+
+    #[test]
+    fn aabb_and_sphere_tests() {
+        let camera = Camera::new(1.0);
+        let frustum = Frustum::from_matrix(&camera.view_proj_matrix());
+
+        let in_front = Aabb { min: Point3::new(-0.5, -0.5, -0.5), max: Point3::new(0.5, 0.5, 0.5) };
+        let behind = Aabb { min: Point3::new(-0.5, -0.5, 4.0), max: Point3::new(0.5, 0.5, 5.0) };
+        let straddling_left = Aabb { min: Point3::new(-20.0, -0.5, -6.0), max: Point3::new(-3.0, 0.5, -5.0) };
+
+        assert!(frustum.intersects_aabb(&in_front));
+        assert!(!frustum.intersects_aabb(&behind));
+        assert!(frustum.intersects_aabb(&straddling_left));
+        assert!(frustum.intersects_sphere(&behind.center(), 2.0));
+        assert!(!frustum.intersects_sphere(&behind.center(), 1.0));
+    }
+
+    #[test]
+    fn rotated_camera_culls_what_was_in_front() {
+        let mut camera = Camera::new(1.0);
+        camera.process_mouse(180.0 / camera.sensitivity, 0.0); // Разворот на 180°
+        let frustum = Frustum::from_matrix(&camera.view_proj_matrix());
+        let origin_box = Aabb { min: Point3::new(-0.5, -0.5, -0.5), max: Point3::new(0.5, 0.5, 0.5) };
+        assert!(!frustum.intersects_aabb(&origin_box));
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt::Write;
+
+use wgpu::{CommandEncoder, Device, TextureFormat, TextureUsages, TextureView};
+use winit::dpi::PhysicalSize;
+
+use crate::error::{CuborumError, Result};
+
+/// Куда сохраняется граф кадра по `Action::DumpRenderGraph`
+pub const RENDER_GRAPH_FILE: &str = "render_graph.dot";
+
+/// Ресурс графа: индекс в списке ресурсов кадра
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// Описание временной текстуры. Текстуры с одинаковым описанием и непересекающимся
+/// временем жизни делят одну текстуру пула.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureDesc {
+    pub size: PhysicalSize<u32>,
+    pub format: TextureFormat,
+    pub usage: TextureUsages,
+    pub sample_count: u32,
+}
+
+impl TextureDesc {
+    /// Цель рендера размера `size`, один сэмпл
+    pub fn target(size: PhysicalSize<u32>, format: TextureFormat) -> Self {
+        Self {
+            size,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            sample_count: 1,
+        }
+    }
+
+    /// Многосэмпловая цель (MSAA)
+    pub fn samples(mut self, count: u32) -> Self {
+        self.sample_count = count;
+        self
+    }
+
+    /// Текстуру ещё и читают шейдеры следующих проходов
+    pub fn sampled(mut self) -> Self {
+        self.usage |= TextureUsages::TEXTURE_BINDING;
+        self
+    }
+
+    /// Создаёт текстуру по описанию (граф делает это сам, вручную – для постоянных текстур)
+    pub fn create(&self, device: &Device, label: &str) -> TextureView {
+        self.create_texture(device, label).create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// То же, но сама текстура: например, чтобы скопировать из неё
+    pub fn create_texture(&self, device: &Device, label: &str) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: self.size.width.max(1),
+                height: self.size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: self.usage,
+            view_formats: &[],
+        })
+    }
+}
+
+enum ResourceKind<'a> {
+    /// Живёт вне графа: буфер или текстура состояния, кадр поверхности
+    Imported(Option<&'a TextureView>),
+    /// Создаётся графом на время кадра
+    Transient(TextureDesc),
+}
+
+struct Resource<'a> {
+    name: &'static str,
+    kind: ResourceKind<'a>,
+}
+
+type Record<'a> = Box<dyn FnOnce(&mut CommandEncoder, &PassResources) + 'a>;
+
+struct Pass<'a> {
+    name: &'static str,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    record: Record<'a>,
+}
+
+/// Граф кадра. Проходы объявляют, что читают и пишут; граф сам выстраивает порядок,
+/// выделяет временные текстуры из пула и записывает проходы в один энкодер.
+/// Собирается заново каждый кадр, поэтому проходы по настройкам просто не добавляются.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource<'a>>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_resource(&mut self, name: &'static str, kind: ResourceKind<'a>) -> ResourceId {
+        self.resources.push(Resource { name, kind });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Внешний ресурс, который проходы берут из состояния сами (буферы, теневая карта)
+    pub fn import(&mut self, name: &'static str) -> ResourceId {
+        self.add_resource(name, ResourceKind::Imported(None))
+    }
+
+    /// Внешняя текстура, которую проходы получают через `PassResources::view`
+    pub fn import_texture(&mut self, name: &'static str, view: &'a TextureView) -> ResourceId {
+        self.add_resource(name, ResourceKind::Imported(Some(view)))
+    }
+
+    /// Временная текстура на время кадра
+    pub fn transient(&mut self, name: &'static str, desc: TextureDesc) -> ResourceId {
+        self.add_resource(name, ResourceKind::Transient(desc))
+    }
+
+    /// Начинает описание прохода; проход добавляется в граф вызовом `record`
+    pub fn pass(&mut self, name: &'static str) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            name,
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Порядок выполнения проходов. Писатели ресурса идут в порядке добавления.
+    /// Читатель, добавленный после писателя, видит его результат и идёт раньше следующего
+    /// писателя; читатель, добавленный раньше всех писателей, идёт после последнего.
+    /// Проход, который и читает, и пишет ресурс, считается писателем (дорисовывает поверх).
+    pub fn order(&self) -> Result<Vec<usize>> {
+        let count = self.passes.len();
+        let mut edges = vec![Vec::new(); count];
+        let mut incoming = vec![0; count];
+        let mut add_edge = |from: usize, to: usize| {
+            if from != to && !edges[from].contains(&to) {
+                edges[from].push(to);
+                incoming[to] += 1;
+            }
+        };
+
+        for resource in 0..self.resources.len() {
+            let id = ResourceId(resource);
+            let writers: Vec<usize> = (0..count).filter(|&p| self.passes[p].writes.contains(&id)).collect();
+            for pair in writers.windows(2) {
+                add_edge(pair[0], pair[1]);
+            }
+            for reader in (0..count).filter(|&p| self.passes[p].reads.contains(&id) && !writers.contains(&p)) {
+                let next = writers.iter().position(|&writer| writer > reader);
+                match next.unwrap_or(writers.len()).checked_sub(1) {
+                    Some(previous) => {
+                        add_edge(writers[previous], reader);
+                        // Запись после чтения: следующий писатель не должен затереть то, что читатель ещё не прочёл
+                        if let Some(next) = next {
+                            add_edge(reader, writers[next]);
+                        }
+                    }
+                    None => {
+                        if let Some(&last) = writers.last() {
+                            add_edge(last, reader);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Алгоритм Кана; из готовых проходов первым идёт добавленный раньше
+        let mut order = Vec::with_capacity(count);
+        let mut ready: Vec<usize> = (0..count).filter(|&p| incoming[p] == 0).collect();
+        while let Some(pass) = ready.iter().copied().min() {
+            ready.retain(|&p| p != pass);
+            order.push(pass);
+            for &next in &edges[pass] {
+                incoming[next] -= 1;
+                if incoming[next] == 0 {
+                    ready.push(next);
+                }
+            }
+        }
+
+        if order.len() < count {
+            let passes = (0..count).filter(|p| !order.contains(p)).map(|p| self.passes[p].name).collect();
+            return Err(CuborumError::RenderGraphCycle { passes });
+        }
+        Ok(order)
+    }
+
+    /// Первый и последний по порядку выполнения проход, использующий каждый ресурс
+    fn lifetimes(&self, order: &[usize]) -> Vec<Option<(usize, usize)>> {
+        let mut lifetimes = vec![None; self.resources.len()];
+        for (step, &pass) in order.iter().enumerate() {
+            let pass = &self.passes[pass];
+            for id in pass.reads.iter().chain(&pass.writes) {
+                let lifetime: &mut Option<(usize, usize)> = &mut lifetimes[id.0];
+                *lifetime = Some(lifetime.map_or((step, step), |(first, _)| (first, step)));
+            }
+        }
+        lifetimes
+    }
+
+    /// Выделяет временные текстуры и записывает проходы в энкодер в порядке зависимостей
+    pub fn execute(self, device: &Device, encoder: &mut CommandEncoder, pool: &mut TransientPool) -> Result<()> {
+        let order = self.order()?;
+        let lifetimes = self.lifetimes(&order);
+
+        let mut transients: Vec<(usize, TextureDesc, (usize, usize))> = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resource)| match (&resource.kind, lifetimes[index]) {
+                (ResourceKind::Transient(desc), Some(lifetime)) => Some((index, *desc, lifetime)),
+                _ => None,
+            })
+            .collect();
+        transients.sort_by_key(|&(_, _, (first, _))| first);
+        let slots = plan_slots(&transients.iter().map(|&(_, desc, lifetime)| (desc, lifetime)).collect::<Vec<_>>());
+        let labels: Vec<&str> = transients.iter().map(|&(index, _, _)| self.resources[index].name).collect();
+        pool.allocate(device, &slots, &labels);
+
+        let mut views: Vec<Option<&TextureView>> = self
+            .resources
+            .iter()
+            .map(|resource| match resource.kind {
+                ResourceKind::Imported(view) => view,
+                ResourceKind::Transient(_) => None,
+            })
+            .collect();
+        for (&(index, _, _), slot) in transients.iter().zip(&slots) {
+            views[index] = Some(&pool.textures[slot.index].1);
+        }
+        let resources = PassResources { names: self.resources.iter().map(|r| r.name).collect(), views };
+
+        let mut passes: Vec<Option<Pass>> = self.passes.into_iter().map(Some).collect();
+        for index in order {
+            let pass = passes[index].take().expect("pass is ordered once");
+            encoder.push_debug_group(pass.name);
+            (pass.record)(encoder, &resources);
+            encoder.pop_debug_group();
+        }
+        Ok(())
+    }
+
+    /// Граф в формате Graphviz: проходы – прямоугольники, ресурсы – овалы
+    /// (временные – пунктиром), рёбра – чтение и запись
+    pub fn to_dot(&self) -> String {
+        let order = self.order().unwrap_or_default();
+        let mut dot = String::from("digraph RenderGraph {\n    rankdir=LR;\n");
+        for (index, resource) in self.resources.iter().enumerate() {
+            let style = match resource.kind {
+                ResourceKind::Imported(_) => "solid",
+                ResourceKind::Transient(_) => "dashed",
+            };
+            let _ = writeln!(dot, "    r{index} [label=\"{}\", shape=ellipse, style={style}];", resource.name);
+        }
+        for (index, pass) in self.passes.iter().enumerate() {
+            let label = match order.iter().position(|&p| p == index) {
+                Some(step) => format!("{}. {}", step + 1, pass.name),
+                None => pass.name.to_string(),
+            };
+            let _ = writeln!(dot, "    p{index} [label=\"{label}\", shape=box];");
+            for read in &pass.reads {
+                let _ = writeln!(dot, "    r{} -> p{index};", read.0);
+            }
+            for write in &pass.writes {
+                let _ = writeln!(dot, "    p{index} -> r{};", write.0);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Описание прохода до добавления в граф
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    name: &'static str,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+}
+
+impl<'a> PassBuilder<'_, 'a> {
+    pub fn reads(mut self, resources: &[ResourceId]) -> Self {
+        self.reads.extend_from_slice(resources);
+        self
+    }
+
+    pub fn writes(mut self, resources: &[ResourceId]) -> Self {
+        self.writes.extend_from_slice(resources);
+        self
+    }
+
+    /// Добавляет проход; `record` вызывается при выполнении графа
+    pub fn record(self, record: impl FnOnce(&mut CommandEncoder, &PassResources) + 'a) {
+        self.graph.passes.push(Pass {
+            name: self.name,
+            reads: self.reads,
+            writes: self.writes,
+            record: Box::new(record),
+        });
+    }
+}
+
+/// Текстуры, доступные проходу во время записи
+pub struct PassResources<'r> {
+    names: Vec<&'static str>,
+    views: Vec<Option<&'r TextureView>>,
+}
+
+impl PassResources<'_> {
+    pub fn view(&self, id: ResourceId) -> &TextureView {
+        self.views[id.0].unwrap_or_else(|| panic!("render graph resource `{}` is not a texture", self.names[id.0]))
+    }
+}
+
+/// Текстура пула, назначенная временному ресурсу
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Slot {
+    desc: TextureDesc,
+    index: usize,
+}
+
+/// Назначает временным ресурсам (по порядку первого использования) слоты: ресурс занимает
+/// слот с тем же описанием, освободившийся до его первого использования, иначе получает новый
+fn plan_slots(transients: &[(TextureDesc, (usize, usize))]) -> Vec<Slot> {
+    let mut busy_until: Vec<(TextureDesc, usize)> = Vec::new();
+    let mut slots = Vec::with_capacity(transients.len());
+    for &(desc, (first, last)) in transients {
+        let free = busy_until.iter().position(|&(slot_desc, until)| slot_desc == desc && until < first);
+        let index = match free {
+            Some(index) => {
+                busy_until[index].1 = last;
+                index
+            }
+            None => {
+                busy_until.push((desc, last));
+                busy_until.len() - 1
+            }
+        };
+        slots.push(Slot { desc, index });
+    }
+    slots
+}
+
+/// Временные текстуры, переживающие кадр. Текстуры, которые кадр не использовал
+/// (например, старого размера после resize), освобождаются.
+#[derive(Default)]
+pub struct TransientPool {
+    textures: Vec<(TextureDesc, TextureView)>,
+}
+
+impl TransientPool {
+    /// Приводит пул к слотам кадра, переиспользуя текстуры с тем же описанием.
+    /// `labels` – имена ресурсов слотов, новая текстура называется по первому.
+    fn allocate(&mut self, device: &Device, slots: &[Slot], labels: &[&str]) {
+        let slot_count = slots.iter().map(|slot| slot.index + 1).max().unwrap_or(0);
+        let mut old = std::mem::take(&mut self.textures);
+        for index in 0..slot_count {
+            let first = slots.iter().position(|slot| slot.index == index).expect("slot indices are dense");
+            let desc = slots[first].desc;
+            let texture = match old.iter().position(|(old_desc, _)| *old_desc == desc) {
+                Some(position) => old.swap_remove(position),
+                None => (desc, desc.create(device, labels[first])),
+            };
+            self.textures.push(texture);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nothing(_: &mut CommandEncoder, _: &PassResources) {}
+
+    fn names(graph: &RenderGraph, order: &[usize]) -> Vec<&'static str> {
+        order.iter().map(|&p| graph.passes[p].name).collect()
+    }
+
+    #[test]
+    fn passes_run_after_what_they_read() {
+        let mut graph = RenderGraph::new();
+        let (shadow, color) = (graph.import("shadow"), graph.import("color"));
+        graph.pass("Main").reads(&[shadow]).writes(&[color]).record(nothing);
+        graph.pass("Overlay").reads(&[color]).writes(&[color]).record(nothing);
+        graph.pass("Shadow").writes(&[shadow]).record(nothing);
+        let order = graph.order().unwrap();
+        assert_eq!(names(&graph, &order), ["Shadow", "Main", "Overlay"]);
+    }
+
+    #[test]
+    fn readers_run_before_the_next_writer() {
+        let mut graph = RenderGraph::new();
+        let (color, bloom) = (graph.import("color"), graph.import("bloom"));
+        graph.pass("Main").writes(&[color]).record(nothing);
+        graph.pass("Copy").reads(&[color, bloom]).record(nothing);
+        graph.pass("Hud").writes(&[color]).record(nothing);
+        graph.pass("Bloom").writes(&[bloom]).record(nothing);
+        // Hud готов раньше Copy, но затёр бы цвет, который Copy ещё не прочёл
+        let order = graph.order().unwrap();
+        assert_eq!(names(&graph, &order), ["Main", "Bloom", "Copy", "Hud"]);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let mut graph = RenderGraph::new();
+        let (a, b) = (graph.import("a"), graph.import("b"));
+        graph.pass("A").reads(&[b]).writes(&[a]).record(nothing);
+        graph.pass("B").reads(&[a]).writes(&[b]).record(nothing);
+        graph.pass("Free").record(nothing);
+        match graph.order() {
+            Err(CuborumError::RenderGraphCycle { passes }) => assert_eq!(passes, ["A", "B"]),
+            other => panic!("expected a cycle, got {:?}", other.map(|order| names(&graph, &order))),
+        }
+    }
+
+    #[test]
+    fn transients_share_textures_when_lifetimes_do_not_overlap() {
+        let hdr = TextureDesc::target(PhysicalSize::new(8, 8), TextureFormat::Rgba16Float);
+        let small = TextureDesc { size: PhysicalSize::new(4, 4), ..hdr };
+        let slots = plan_slots(&[(hdr, (0, 1)), (hdr, (1, 2)), (hdr, (2, 3)), (small, (3, 4))]);
+        let indices: Vec<usize> = slots.iter().map(|slot| slot.index).collect();
+        // Второй пересекается с первым в шаге 1, третий начинается после конца первого
+        assert_eq!(indices, [0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn dot_lists_passes_in_order() {
+        let mut graph = RenderGraph::new();
+        let depth = graph.transient("depth", TextureDesc::target(PhysicalSize::new(8, 8), TextureFormat::Depth32Float));
+        graph.pass("Main").writes(&[depth]).record(nothing);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph RenderGraph {"));
+        assert!(dot.contains("r0 [label=\"depth\", shape=ellipse, style=dashed];"));
+        assert!(dot.contains("p0 [label=\"1. Main\", shape=box];"));
+        assert!(dot.contains("p0 -> r0;"));
+    }
+}
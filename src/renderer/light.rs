@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+use crate::renderer::voxel::VoxelGrid;
+
+/// Максимальный уровень освещения
+pub const MAX_LIGHT: u8 = 15;
+
+/// Каналы освещения: небесный свет и цветной свет блоков
+pub const SKY: usize = 0;
+pub const RED: usize = 1;
+pub const GREEN: usize = 2;
+pub const BLUE: usize = 3;
+const CHANNELS: usize = 4;
+
+const NEIGHBOURS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+const DOWN: usize = 3;
+
+/// Объём освещения той же размерности, что и `VoxelGrid`
+pub struct LightVolume {
+    pub size: usize,
+    pub levels: Vec<[u8; CHANNELS]>,
+}
+
+impl LightVolume {
+    /// Полный расчёт освещения для сетки
+    pub fn new(grid: &VoxelGrid) -> Self {
+        let mut volume = Self {
+            size: grid.size,
+            levels: vec![[0; CHANNELS]; grid.data.len()],
+        };
+
+        let mut queues: [VecDeque<[i32; 3]>; CHANNELS] = Default::default();
+        for z in 0..grid.size {
+            for y in 0..grid.size {
+                for x in 0..grid.size {
+                    let pos = [x as i32, y as i32, z as i32];
+                    volume.seed(grid, pos, &mut queues);
+                }
+            }
+        }
+        for (channel, queue) in queues.iter_mut().enumerate() {
+            volume.propagate(grid, channel, queue);
+        }
+        volume
+    }
+
+    fn index(&self, pos: [i32; 3]) -> Option<usize> {
+        let size = self.size as i32;
+        if pos.iter().any(|&c| c < 0 || c >= size) {
+            return None;
+        }
+        Some(pos[2] as usize * self.size * self.size + pos[1] as usize * self.size + pos[0] as usize)
+    }
+
+    /// Источники света в ячейке: открытое небо сверху и светящиеся воксели
+    fn seed(&mut self, grid: &VoxelGrid, pos: [i32; 3], queues: &mut [VecDeque<[i32; 3]>; CHANNELS]) {
+        let index = self.index(pos).expect("seed inside the grid");
+        let voxel = grid.data[index];
+
+        if pos[1] == self.size as i32 - 1 && voxel.is_empty() {
+            self.levels[index][SKY] = MAX_LIGHT;
+            queues[SKY].push_back(pos);
+        }
+
+        let emission = voxel.emission();
+        for (i, &level) in emission.iter().enumerate() {
+            if level > 0 {
+                self.levels[index][RED + i] = level;
+                queues[RED + i].push_back(pos);
+            }
+        }
+    }
+
+    /// Уровень, который свет принесёт в соседа. Небесный свет идёт вниз без потерь.
+    fn spread(channel: usize, direction: usize, level: u8) -> u8 {
+        if channel == SKY && direction == DOWN && level == MAX_LIGHT {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(1)
+        }
+    }
+
+    /// BFS-распространение света от ячеек очереди
+    fn propagate(&mut self, grid: &VoxelGrid, channel: usize, queue: &mut VecDeque<[i32; 3]>) {
+        while let Some(pos) = queue.pop_front() {
+            let level = self.levels[self.index(pos).unwrap()][channel];
+            for (direction, offset) in NEIGHBOURS.iter().enumerate() {
+                let next = [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]];
+                let Some(next_index) = self.index(next) else { continue };
+                if grid.is_solid(next) {
+                    continue;
+                }
+                let new_level = Self::spread(channel, direction, level);
+                if self.levels[next_index][channel] < new_level {
+                    self.levels[next_index][channel] = new_level;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Снимает свет, пришедший из ячеек очереди, и собирает края для повторного распространения
+    fn remove(&mut self, grid: &VoxelGrid, channel: usize, mut queue: VecDeque<([i32; 3], u8)>) {
+        let mut refill = VecDeque::new();
+        while let Some((pos, level)) = queue.pop_front() {
+            for (direction, offset) in NEIGHBOURS.iter().enumerate() {
+                let next = [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]];
+                let Some(next_index) = self.index(next) else { continue };
+                let next_level = self.levels[next_index][channel];
+                if next_level == 0 {
+                    continue;
+                }
+                let carried = next_level < level
+                    || (channel == SKY && direction == DOWN && level == MAX_LIGHT && next_level == MAX_LIGHT);
+                if carried && !self.is_source(grid, next, channel) {
+                    self.levels[next_index][channel] = 0;
+                    queue.push_back((next, next_level));
+                } else {
+                    refill.push_back(next);
+                }
+            }
+        }
+        self.propagate(grid, channel, &mut refill);
+    }
+
+    fn is_source(&self, grid: &VoxelGrid, pos: [i32; 3], channel: usize) -> bool {
+        let voxel = grid.get(pos[0] as usize, pos[1] as usize, pos[2] as usize);
+        if channel == SKY {
+            pos[1] == self.size as i32 - 1 && voxel.is_empty()
+        } else {
+            voxel.emission()[channel - RED] > 0
+        }
+    }
+
+    /// Инкрементально обновляет освещение после изменения ячейки `pos` (сетка уже изменена)
+    pub fn update(&mut self, grid: &VoxelGrid, x: usize, y: usize, z: usize) {
+        let pos = [x as i32, y as i32, z as i32];
+        let index = self.index(pos).expect("update inside the grid");
+
+        // 1. Убираем весь свет, который проходил через ячейку
+        let old = self.levels[index];
+        self.levels[index] = [0; CHANNELS];
+        for (channel, &level) in old.iter().enumerate() {
+            if level > 0 {
+                self.remove(grid, channel, VecDeque::from([(pos, level)]));
+            }
+        }
+
+        // 2. Новые источники в самой ячейке
+        let mut queues: [VecDeque<[i32; 3]>; CHANNELS] = Default::default();
+        self.seed(grid, pos, &mut queues);
+
+        // 3. Пустая ячейка снова принимает свет от соседей
+        if !grid.is_solid(pos) {
+            for offset in NEIGHBOURS.iter() {
+                let next = [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]];
+                if let Some(next_index) = self.index(next) {
+                    for (channel, queue) in queues.iter_mut().enumerate() {
+                        if self.levels[next_index][channel] > 0 {
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (channel, queue) in queues.iter_mut().enumerate() {
+            self.propagate(grid, channel, queue);
+        }
+    }
+
+    /// Упаковка для GPU: по 4 бита на канал (sky, r, g, b)
+    pub fn packed(&self) -> Vec<u32> {
+        self.levels
+            .iter()
+            .map(|l| l[SKY] as u32 | (l[RED] as u32) << 4 | (l[GREEN] as u32) << 8 | (l[BLUE] as u32) << 12)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::voxel::{Voxel, VOXEL_EMISSIVE, VOXEL_SOLID};
+
+    fn solid() -> Voxel {
+        Voxel::new(VOXEL_SOLID, 128, 128, 128, 255)
+    }
+
+    fn lamp() -> Voxel {
+        Voxel::new(VOXEL_EMISSIVE, 255, 0, 255, 255)
+    }
+
+    fn level(light: &LightVolume, x: i32, y: i32, z: i32) -> [u8; CHANNELS] {
+        light.levels[light.index([x, y, z]).unwrap()]
+    }
+
+    #[test]
+    fn open_column_receives_full_sky_light() {
+        let grid = VoxelGrid::new(4);
+        let light = LightVolume::new(&grid);
+        assert!(light.levels.iter().all(|l| l[SKY] == MAX_LIGHT));
+    }
+
+    #[test]
+    fn roof_blocks_sky_light() {
+        let mut grid = VoxelGrid::new(5);
+        for z in 0..5 {
+            for x in 0..5 {
+                grid.set(x, 3, z, solid());
+            }
+        }
+        let light = LightVolume::new(&grid);
+        assert_eq!(level(&light, 2, 4, 2)[SKY], MAX_LIGHT);
+        assert_eq!(level(&light, 2, 3, 2)[SKY], 0);
+        assert_eq!(level(&light, 2, 0, 2)[SKY], 0);
+    }
+
+    #[test]
+    fn block_light_falls_off_by_one_per_step_and_keeps_colour() {
+        let mut grid = VoxelGrid::new(8);
+        grid.set(0, 0, 0, lamp());
+        let light = LightVolume::new(&grid);
+        assert_eq!(level(&light, 0, 0, 0)[RED], MAX_LIGHT);
+        assert_eq!(level(&light, 3, 0, 0)[RED], MAX_LIGHT - 3);
+        assert_eq!(level(&light, 1, 1, 1)[BLUE], MAX_LIGHT - 3);
+        assert_eq!(level(&light, 3, 0, 0)[GREEN], 0);
+    }
+
+    #[test]
+    fn incremental_updates_match_full_recompute() {
+        let mut grid = VoxelGrid::new(8);
+        grid.fill_with_test_pattern();
+        let mut light = LightVolume::new(&grid);
+
+        let edits = [
+            (3, 5, 3, lamp()),
+            (4, 7, 4, solid()),
+            (3, 5, 3, Voxel::empty()),
+            (2, 3, 2, Voxel::empty()),
+            (4, 7, 4, Voxel::empty()),
+            (6, 4, 6, lamp()),
+        ];
+        for (x, y, z, voxel) in edits {
+            grid.set(x, y, z, voxel);
+            light.update(&grid, x, y, z);
+            assert_eq!(light.levels, LightVolume::new(&grid).levels);
+        }
+    }
+}
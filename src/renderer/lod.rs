@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use nalgebra::Point3;
+
+use crate::renderer::frustum::Aabb;
+use crate::renderer::voxel::{Voxel, VoxelGrid, COLOR_SHIFTS, VOXEL_SOLID};
+
+/// Количество уровней детализации: 1×, 2×, 4×, 8×
+pub const LOD_COUNT: usize = 4;
+
+/// Как выбирается цвет грубого вокселя
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LodColor {
+    Majority, // Самый частый цвет среди сплошных вокселей
+    Average,  // Среднее по сплошным вокселям
+}
+
+/// Настройки LOD
+#[derive(Clone, Copy, Debug)]
+pub struct LodSettings {
+    pub enabled: bool,
+    pub color: LodColor,
+    /// Расстояния (в мировых единицах) от камеры до центра чанка, после которых
+    /// включается уровень 1, 2 и 3
+    pub distances: [f32; LOD_COUNT - 1],
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: LodColor::Average,
+            distances: [3.0, 6.0, 12.0],
+        }
+    }
+}
+
+/// Во сколько раз уровень грубее исходной сетки
+pub fn lod_factor(level: usize) -> usize {
+    1 << level
+}
+
+/// Уровень детализации по расстоянию
+pub fn select_lod(settings: &LodSettings, distance: f32) -> usize {
+    if !settings.enabled {
+        return 0;
+    }
+    settings.distances.iter().take_while(|&&d| distance >= d).count()
+}
+
+/// Уровень детализации чанка для позиции камеры
+pub fn chunk_lod(settings: &LodSettings, bounds: &Aabb, camera_position: &Point3<f32>) -> usize {
+    select_lod(settings, (bounds.center() - camera_position).norm())
+}
+
+/// Уменьшает сетку в `factor` раз: ячейка сплошная, если сплошных вокселей в ней не меньше половины.
+/// Размер сетки должен делиться на `factor`, иначе масштаб уровней не совпадёт.
+pub fn downsample(grid: &VoxelGrid, factor: usize, color: LodColor) -> VoxelGrid {
+    debug_assert_eq!(grid.size % factor, 0, "grid size {} is not a multiple of {factor}", grid.size);
+    let size = grid.size / factor;
+    let mut coarse = VoxelGrid::new(size);
+
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                coarse.set(x, y, z, downsample_cell(grid, factor, [x, y, z], color));
+            }
+        }
+    }
+    coarse
+}
+
+/// Ячейка `[x, y, z]` сетки, уменьшенной в `factor` раз
+fn downsample_cell(grid: &VoxelGrid, factor: usize, [x, y, z]: [usize; 3], color: LodColor) -> Voxel {
+    let mut solids = Vec::new();
+    for fz in z * factor..(z + 1) * factor {
+        for fy in y * factor..(y + 1) * factor {
+            for fx in x * factor..(x + 1) * factor {
+                let voxel = grid.get(fx, fy, fz);
+                if !voxel.is_empty() {
+                    solids.push(*voxel);
+                }
+            }
+        }
+    }
+
+    if solids.is_empty() || solids.len() * 2 < factor.pow(3) {
+        return Voxel::empty();
+    }
+    merge(&solids, color)
+}
+
+fn merge(solids: &[Voxel], color: LodColor) -> Voxel {
+    match color {
+        LodColor::Majority => {
+            let mut counts: HashMap<(u32, u32), usize> = HashMap::new();
+            for voxel in solids {
+                *counts.entry((voxel.voxel_type, voxel.color)).or_default() += 1;
+            }
+            // При равенстве берём меньший ключ, чтобы результат был детерминированным
+            let ((voxel_type, color), _) = counts
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                .expect("non-empty solids");
+            Voxel { voxel_type, color }
+        }
+        LodColor::Average => {
+            let mut sum = [0u32; 4];
+            for voxel in solids {
+                for (i, channel) in sum.iter_mut().enumerate() {
+                    *channel += (voxel.color >> COLOR_SHIFTS[i]) & 0xFF;
+                }
+            }
+            let n = solids.len() as u32;
+            let [r, g, b, a] = sum.map(|c| ((c + n / 2) / n) as u8);
+            // Свет блоков считается по исходной сетке, грубым уровням нужен только цвет
+            Voxel::new(VOXEL_SOLID, r, g, b, a)
+        }
+    }
+}
+
+/// Грубые копии сетки для уровней 1..LOD_COUNT
+pub struct LodGrids {
+    pub levels: Vec<VoxelGrid>,
+}
+
+impl LodGrids {
+    pub fn new(grid: &VoxelGrid, color: LodColor) -> Self {
+        Self {
+            levels: (1..LOD_COUNT).map(|level| downsample(grid, lod_factor(level), color)).collect(),
+        }
+    }
+
+    /// Пересчитывает на каждом уровне только грубую ячейку, в которую попал изменённый воксель `cell`
+    pub fn update(&mut self, grid: &VoxelGrid, cell: [usize; 3], color: LodColor) {
+        for (level, coarse) in (1..).zip(&mut self.levels) {
+            let factor = lod_factor(level);
+            let [x, y, z] = cell.map(|c| c / factor);
+            coarse.set(x, y, z, downsample_cell(grid, factor, [x, y, z], color));
+        }
+    }
+
+    /// Сетка для уровня `level` (0 – исходная)
+    pub fn grid<'a>(&'a self, base: &'a VoxelGrid, level: usize) -> &'a VoxelGrid {
+        if level == 0 {
+            base
+        } else {
+            &self.levels[level - 1]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn majority_occupancy_decides_solidity() {
+        let mut grid = VoxelGrid::new(4);
+        // В левом нижнем блоке 2×2×2 заполнено 4 из 8 – сплошной, в соседнем 3 из 8 – пустой
+        for (x, y, z) in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0), (2, 0, 0), (3, 0, 0), (2, 1, 0)] {
+            grid.set(x, y, z, Voxel::new(VOXEL_SOLID, 10, 20, 30, 255));
+        }
+        let coarse = downsample(&grid, 2, LodColor::Average);
+        assert_eq!(coarse.size, 2);
+        assert!(!coarse.get(0, 0, 0).is_empty());
+        assert!(coarse.get(1, 0, 0).is_empty());
+        assert_eq!(coarse.voxel_size(), grid.voxel_size() * 2.0);
+    }
+
+    #[test]
+    fn average_and_majority_colours() {
+        let mut grid = VoxelGrid::new(2);
+        for (i, (x, y, z)) in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0), (0, 0, 1)].into_iter().enumerate() {
+            let red = if i < 3 { 200 } else { 100 };
+            grid.set(x, y, z, Voxel::new(VOXEL_SOLID, red, 0, 0, 255));
+        }
+        let average = downsample(&grid, 2, LodColor::Average);
+        assert_eq!(average.get(0, 0, 0).color >> 24, 160);
+        let majority = downsample(&grid, 2, LodColor::Majority);
+        assert_eq!(majority.get(0, 0, 0).color >> 24, 200);
+    }
+
+    #[test]
+    fn update_matches_full_downsample() {
+        let mut grid = VoxelGrid::new(16);
+        grid.fill_with_test_pattern();
+        let mut lods = LodGrids::new(&grid, LodColor::Average);
+        // Новый сплошной блок 2×2 над полом и перекраска вокселя пола
+        for cell in [[2, 8, 4], [3, 8, 4], [2, 9, 4], [3, 9, 4], [5, 2, 5]] {
+            grid.set(cell[0], cell[1], cell[2], Voxel::new(VOXEL_SOLID, 0, 0, 255, 255));
+            lods.update(&grid, cell, LodColor::Average);
+        }
+        for (updated, full) in lods.levels.iter().zip(LodGrids::new(&grid, LodColor::Average).levels) {
+            assert!(updated.data.iter().zip(&full.data).all(|(a, b)| (a.voxel_type, a.color) == (b.voxel_type, b.color)));
+        }
+    }
+
+    #[test]
+    fn lod_is_picked_by_distance() {
+        let settings = LodSettings::default();
+        assert_eq!(select_lod(&settings, 0.0), 0);
+        assert_eq!(select_lod(&settings, 4.0), 1);
+        assert_eq!(select_lod(&settings, 7.0), 2);
+        assert_eq!(select_lod(&settings, 50.0), 3);
+        let disabled = LodSettings { enabled: false, ..settings };
+        assert_eq!(select_lod(&disabled, 50.0), 0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::renderer::vertex::Vertex;
+use crate::renderer::voxel::{VoxelGrid, VOXEL_EMISSIVE};
+
+/// Настройки построения меша из сетки вокселей
+#[derive(Clone, Copy, Debug)]
+pub struct MeshOptions {
+    pub ambient_occlusion: bool, // Повершинное затенение (AO)
+    pub seal_borders: bool,      // Не отбрасывать грани на границе области (закрывает щели между LOD)
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        Self {
+            ambient_occlusion: true,
+            seal_borders: false,
+        }
+    }
+}
+
+/// CPU-меш: вершины и индексы треугольников
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+/// Описание грани куба: нормаль и две касательные оси (u × v = normal)
+struct Face {
+    normal: [i32; 3],
+    u: [i32; 3],
+    v: [i32; 3],
+}
+
+const FACES: [Face; 6] = [
+    Face { normal: [1, 0, 0], u: [0, 1, 0], v: [0, 0, 1] },  // +X
+    Face { normal: [-1, 0, 0], u: [0, 0, 1], v: [0, 1, 0] }, // -X
+    Face { normal: [0, 1, 0], u: [0, 0, 1], v: [1, 0, 0] },  // +Y
+    Face { normal: [0, -1, 0], u: [1, 0, 0], v: [0, 0, 1] }, // -Y
+    Face { normal: [0, 0, 1], u: [1, 0, 0], v: [0, 1, 0] },  // +Z
+    Face { normal: [0, 0, -1], u: [0, 1, 0], v: [1, 0, 0] }, // -Z
+];
+
+/// Углы квада в координатах (u, v), против часовой стрелки если смотреть снаружи
+const CORNERS: [(i32, i32); 4] = [(0, 0), (1, 0), (1, 1), (0, 1)];
+
+/// Уровень затенения вершины по трём соседям: 0 – полностью закрыта, 3 – открыта
+pub fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u8 {
+    if side1 && side2 {
+        return 0;
+    }
+    3 - (side1 as u8 + side2 as u8 + corner as u8)
+}
+
+/// Индексы двух треугольников квада. Диагональ выбирается по значениям AO,
+/// чтобы интерполяция не давала анизотропных артефактов.
+pub fn quad_indices(base: u32, ao: [u8; 4]) -> [u32; 6] {
+    if ao[0] as u32 + ao[2] as u32 > ao[1] as u32 + ao[3] as u32 {
+        // Переворачиваем квад: диагональ 1–3
+        [base + 1, base + 2, base + 3, base + 3, base, base + 1]
+    } else {
+        [base, base + 1, base + 2, base + 2, base + 3, base]
+    }
+}
+
+fn add(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [i32; 3], s: i32) -> [i32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+/// Строит меш видимых граней для ячеек в диапазоне `[min, max)`
+/// (грани между двумя сплошными вокселями отбрасываются, соседи за границей берутся из всей сетки)
+pub fn build_region_mesh(grid: &VoxelGrid, options: MeshOptions, min: [usize; 3], max: [usize; 3]) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let voxel_size = grid.voxel_size();
+    let origin = grid.origin();
+
+    for z in min[2]..max[2] {
+        for y in min[1]..max[1] {
+            for x in min[0]..max[0] {
+                let voxel = grid.get(x, y, z);
+                if voxel.is_empty() {
+                    continue;
+                }
+                let color = voxel.unpack_color();
+                let emission = if voxel.voxel_type == VOXEL_EMISSIVE { 1.0 } else { 0.0 };
+                let p = [x as i32, y as i32, z as i32];
+
+                for face in FACES.iter() {
+                    let front = add(p, face.normal);
+                    let outside = (0..3).any(|a| front[a] < min[a] as i32 || front[a] >= max[a] as i32);
+                    if grid.is_solid(front) && !(options.seal_borders && outside) {
+                        continue;
+                    }
+
+                    // Угол грани с минимальными (u, v): для положительной нормали сдвигаемся на 1
+                    let face_origin = add(p, face.normal.map(|n| n.max(0)));
+
+                    let mut ao = [3u8; 4];
+                    let base = vertices.len() as u32;
+                    for (i, &(cu, cv)) in CORNERS.iter().enumerate() {
+                        if options.ambient_occlusion {
+                            let du = scale(face.u, cu * 2 - 1);
+                            let dv = scale(face.v, cv * 2 - 1);
+                            let side1 = grid.is_solid(add(front, du));
+                            let side2 = grid.is_solid(add(front, dv));
+                            let corner = grid.is_solid(add(add(front, du), dv));
+                            ao[i] = vertex_ao(side1, side2, corner);
+                        }
+
+                        let corner_pos = add(add(face_origin, scale(face.u, cu)), scale(face.v, cv));
+                        vertices.push(Vertex {
+                            position: [
+                                origin[0] + corner_pos[0] as f32 * voxel_size,
+                                origin[1] + corner_pos[1] as f32 * voxel_size,
+                                origin[2] + corner_pos[2] as f32 * voxel_size,
+                            ],
+                            color,
+                            ao: ao[i] as f32 / 3.0,
+                            normal: face.normal.map(|n| n as f32),
+                            emission,
+                        });
+                    }
+
+                    indices.extend_from_slice(&quad_indices(base, ao));
+                }
+            }
+        }
+    }
+
+    Mesh { vertices, indices }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::voxel::Voxel;
+
+    fn solid() -> Voxel {
+        Voxel::new(1, 255, 255, 255, 255)
+    }
+
+    #[test]
+    fn ao_open_corner_is_fully_lit() {
+        assert_eq!(vertex_ao(false, false, false), 3);
+    }
+
+    #[test]
+    fn ao_single_neighbour() {
+        assert_eq!(vertex_ao(true, false, false), 2);
+        assert_eq!(vertex_ao(false, true, false), 2);
+        assert_eq!(vertex_ao(false, false, true), 2);
+    }
+
+    #[test]
+    fn ao_side_and_corner() {
+        assert_eq!(vertex_ao(true, false, true), 1);
+        assert_eq!(vertex_ao(false, true, true), 1);
+    }
+
+    #[test]
+    fn ao_two_sides_fully_occlude_regardless_of_corner() {
+        assert_eq!(vertex_ao(true, true, false), 0);
+        assert_eq!(vertex_ao(true, true, true), 0);
+    }
+
+    #[test]
+    fn quad_splits_along_darker_diagonal() {
+        // Диагональ проходит через углы с меньшей суммой AO
+        assert_eq!(quad_indices(0, [3, 3, 3, 3]), [0, 1, 2, 2, 3, 0]);
+        assert_eq!(quad_indices(4, [3, 0, 3, 0]), [5, 6, 7, 7, 4, 5]);
+        assert_eq!(quad_indices(0, [0, 3, 0, 3]), [0, 1, 2, 2, 3, 0]);
+    }
+
+    #[test]
+    fn single_voxel_has_six_unoccluded_faces() {
+        let mut grid = VoxelGrid::new(3);
+        grid.set(1, 1, 1, solid());
+        let mesh = build_region_mesh(&grid, MeshOptions::default(), [0; 3], [grid.size; 3]);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        assert!(mesh.vertices.iter().all(|v| v.ao == 1.0));
+    }
+
+    #[test]
+    fn top_face_next_to_wall_is_darkened_on_edge() {
+        // Пол из двух вокселей и стена над правым: верх левого вокселя затеняется у стены
+        let mut grid = VoxelGrid::new(3);
+        grid.set(0, 0, 0, solid());
+        grid.set(1, 0, 0, solid());
+        grid.set(1, 1, 0, solid());
+        let mesh = build_region_mesh(&grid, MeshOptions::default(), [0; 3], [grid.size; 3]);
+
+        let top: Vec<&Vertex> = mesh
+            .vertices
+            .chunks(4)
+            .find(|quad| {
+                quad.iter().all(|v| v.position[1] == grid.origin()[1] + grid.voxel_size())
+                    && quad.iter().all(|v| v.position[0] <= grid.origin()[0] + grid.voxel_size())
+            })
+            .expect("top face of the left voxel")
+            .iter()
+            .collect();
+
+        let edge_x = grid.origin()[0] + grid.voxel_size();
+        for v in top {
+            if v.position[0] == edge_x {
+                assert_eq!(v.ao, 2.0 / 3.0);
+            } else {
+                assert_eq!(v.ao, 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn ao_can_be_disabled() {
+        let mut grid = VoxelGrid::new(3);
+        grid.set(0, 0, 0, solid());
+        grid.set(1, 0, 0, solid());
+        grid.set(1, 1, 0, solid());
+        let options = MeshOptions { ambient_occlusion: false, ..Default::default() };
+        let mesh = build_region_mesh(&grid, options, [0; 3], [grid.size; 3]);
+        assert!(mesh.vertices.iter().all(|v| v.ao == 1.0));
+    }
+
+    #[test]
+    fn sealed_region_keeps_border_faces() {
+        let mut grid = VoxelGrid::new(2);
+        grid.set(0, 0, 0, solid());
+        grid.set(1, 0, 0, solid());
+        let options = MeshOptions { seal_borders: true, ..Default::default() };
+        let mesh = build_region_mesh(&grid, options, [0; 3], [1, 2, 2]);
+        assert_eq!(mesh.indices.len(), 6 * 6);
+    }
+
+    #[test]
+    fn hidden_faces_are_culled() {
+        let mut grid = VoxelGrid::new(2);
+        grid.set(0, 0, 0, solid());
+        grid.set(1, 0, 0, solid());
+        let mesh = build_region_mesh(&grid, MeshOptions::default(), [0; 3], [grid.size; 3]);
+        assert_eq!(mesh.indices.len(), 10 * 6);
+    }
+}
@@ -1,5 +1,25 @@
 pub mod camera;
+pub mod camera_path;
+pub mod chunk;
+pub mod culling;
+pub mod frustum;
+pub mod graph;
+pub mod light;
+pub mod lod;
+pub mod mesh;
+pub mod msaa;
+pub mod orbit;
+pub mod pathtrace;
+pub mod picking;
 pub mod pipeline;
+pub mod post;
+pub mod raymarch;
+pub mod recovery;
+pub mod shaders;
+pub mod shadow;
 pub mod state;
+pub mod texture;
+pub mod timer;
 pub mod vertex;
+pub mod viewport;
 pub mod voxel;
@@ -0,0 +1,54 @@
+/// Числа сэмплов MSAA, из которых можно выбирать
+pub const MSAA_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// Текущее число сэмплов MSAA и те из `MSAA_SAMPLE_COUNTS`, что умеет адаптер
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Msaa {
+    pub samples: u32,
+    pub supported: Vec<u32>, // По возрастанию, 1 есть всегда
+}
+
+impl Msaa {
+    /// `requested` урезается до ближайшего снизу числа, для которого `supported` истинно
+    pub fn new(requested: u32, supported: impl Fn(u32) -> bool) -> Self {
+        let supported: Vec<u32> = MSAA_SAMPLE_COUNTS.into_iter().filter(|&count| count == 1 || supported(count)).collect();
+        let mut msaa = Self { samples: 1, supported };
+        msaa.samples = msaa.clamp(requested);
+        msaa
+    }
+
+    /// Наибольшее поддерживаемое число сэмплов, не превышающее `requested`
+    pub fn clamp(&self, requested: u32) -> u32 {
+        self.supported.iter().copied().filter(|&count| count <= requested).max().unwrap_or(1)
+    }
+
+    /// Следующее поддерживаемое число сэмплов (после наибольшего – снова 1)
+    pub fn next(&self) -> u32 {
+        self.supported.iter().copied().find(|&count| count > self.samples).unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requested_count_is_clamped_to_supported() {
+        let msaa = Msaa::new(8, |count| count == 4);
+        assert_eq!(msaa.supported, [1, 4]);
+        assert_eq!(msaa.samples, 4);
+        assert_eq!(msaa.clamp(3), 1);
+        assert_eq!(Msaa::new(0, |_| true).samples, 1);
+    }
+
+    #[test]
+    fn next_cycles_through_supported() {
+        let mut msaa = Msaa::new(1, |count| count != 2);
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            msaa.samples = msaa.next();
+            seen.push(msaa.samples);
+        }
+        assert_eq!(seen, [4, 8, 1, 4]);
+    }
+}
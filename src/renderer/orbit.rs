@@ -0,0 +1,100 @@
+use nalgebra::{Point3, Vector2};
+
+/// Ближе этого к цели камера не подлетает
+pub const MIN_DISTANCE: f32 = 0.1;
+/// Градусов поворота на пиксель перетаскивания
+const ROTATE_SENSITIVITY: f32 = 0.3;
+/// Сдвиг цели на пиксель перетаскивания (в долях расстояния до цели)
+const PAN_SENSITIVITY: f32 = 0.002;
+/// Приближение на одно деление колеса (логарифм множителя расстояния)
+const ZOOM_SENSITIVITY: f32 = 0.15;
+
+/// Кнопки мыши, которыми управляется орбита
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitDrag {
+    Rotate, // Левая кнопка
+    Pan,    // Средняя кнопка
+}
+
+/// Движение орбиты за один кадр
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitStep {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub zoom: f32,
+    pub pan: Vector2<f32>,
+}
+
+/// Состояние орбитальной камеры: цель, расстояние и ещё не пройденная часть жестов
+#[derive(Clone, Copy, Debug)]
+pub struct Orbit {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub damping: f32, // Доля оставшегося движения, которая сохраняется за 1/60 с
+    pub pending_yaw: f32,
+    pub pending_pitch: f32,
+    pub pending_zoom: f32,
+    pub pending_pan: Vector2<f32>,
+    pub drag: Option<OrbitDrag>,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            target: Point3::origin(),
+            distance: 3.0,
+            damping: 0.8,
+            pending_yaw: 0.0,
+            pending_pitch: 0.0,
+            pending_zoom: 0.0,
+            pending_pan: Vector2::zeros(),
+            drag: None,
+        }
+    }
+}
+
+impl Orbit {
+    /// Перемещение мыши в пикселях (y вниз)
+    pub fn drag_by(&mut self, dx: f32, dy: f32) {
+        match self.drag {
+            Some(OrbitDrag::Rotate) => {
+                self.pending_yaw += dx * ROTATE_SENSITIVITY;
+                self.pending_pitch -= dy * ROTATE_SENSITIVITY;
+            }
+            Some(OrbitDrag::Pan) => {
+                self.pending_pan += Vector2::new(dx, dy) * PAN_SENSITIVITY;
+            }
+            None => (),
+        }
+    }
+
+    /// Прокрутка колеса в делениях (положительная – приблизить)
+    pub fn zoom_by(&mut self, lines: f32) {
+        self.pending_zoom += lines * ZOOM_SENSITIVITY;
+    }
+
+    /// Забирает часть оставшегося движения за `dt` секунд. Доля зависит от времени,
+    /// а не от числа кадров, и в сумме камера проходит ровно величину жеста.
+    pub fn step(&mut self, dt: f32) -> OrbitStep {
+        let k = 1.0 - self.damping.powf(dt * 60.0);
+        let step = OrbitStep {
+            yaw: self.pending_yaw * k,
+            pitch: self.pending_pitch * k,
+            zoom: self.pending_zoom * k,
+            pan: self.pending_pan * k,
+        };
+        self.pending_yaw -= step.yaw;
+        self.pending_pitch -= step.pitch;
+        self.pending_zoom -= step.zoom;
+        self.pending_pan -= step.pan;
+        step
+    }
+
+    /// Мгновенно останавливает инерцию
+    pub fn stop(&mut self) {
+        self.pending_yaw = 0.0;
+        self.pending_pitch = 0.0;
+        self.pending_zoom = 0.0;
+        self.pending_pan = Vector2::zeros();
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+
+use bytemuck::{Pod, Zeroable};
+use nalgebra::Matrix4;
+use wgpu::{BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue, RenderPipeline};
+use winit::dpi::PhysicalSize;
+
+use crate::error::{CuborumError, Result};
+use crate::renderer::camera::Camera;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_pathtrace_display_pipeline, create_pathtrace_pipeline, layout};
+use crate::renderer::post::PostProcess;
+use crate::renderer::shadow::Sun;
+use crate::renderer::texture::SceneTarget;
+use crate::renderer::voxel::Voxel;
+
+/// Сэмплов на пиксель за кадр
+pub const SAMPLES_PER_FRAME: u32 = 4;
+/// Размер рабочей группы в pathtrace.wgsl
+pub const WORKGROUP_SIZE: u32 = 8;
+
+/// Uniform трассировщика путей (совпадает с `PathTrace` в pathtrace.wgsl)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct PathTraceUniform {
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub camera_position: [f32; 4],
+    pub sun_direction: [f32; 4],
+    pub size: [u32; 2],
+    pub frame: u32,
+    pub samples_per_frame: u32,
+}
+
+/// Прогрессивный рендер: сохранить PNG, когда накопится `samples` сэмплов
+pub struct ProgressiveTarget {
+    pub samples: u32,
+    pub path: PathBuf,
+}
+
+/// Трассировщик путей по буферу вокселей с накоплением, пока камера неподвижна
+pub struct PathTracer {
+    pub compute_pipeline: ComputePipeline,
+    pub display_pipeline: RenderPipeline,
+    pub compute_bind_group_layout: BindGroupLayout,
+    pub display_bind_group_layout: BindGroupLayout,
+    pub uniform_buffer: Buffer,
+    pub accumulation_buffer: Buffer,
+    pub compute_bind_group: BindGroup,
+    pub display_bind_group: BindGroup,
+    pub voxel_buffer: Buffer,
+    pub light_buffer: Buffer,
+    pub grid_buffer: Buffer,
+    pub size: PhysicalSize<u32>,
+    pub frame: u32,                   // Кадров накоплено с последнего сброса
+    pub last_view_proj: Matrix4<f32>, // Для определения движения камеры
+    pub progressive: Option<ProgressiveTarget>,
+}
+
+impl PathTracer {
+    /// Раскладка трассировки: uniform, воксели, освещение, сетка, накопление
+    pub fn compute_layout_entries() -> [wgpu::BindGroupLayoutEntry; 5] {
+        let compute = wgpu::ShaderStages::COMPUTE;
+        [
+            layout::uniform(0, compute),
+            layout::storage_array::<Voxel>(1, compute, true), // Воксели
+            layout::storage(2, compute, true),                // Освещение
+            layout::uniform(3, compute),                      // Параметры сетки
+            layout::storage(4, compute, false),               // Накопление
+        ]
+    }
+
+    /// Раскладка вывода: uniform и накопленные сэмплы
+    pub fn display_layout_entries() -> [wgpu::BindGroupLayoutEntry; 2] {
+        let fragment = wgpu::ShaderStages::FRAGMENT;
+        [layout::uniform(0, fragment), layout::storage(1, fragment, true)]
+    }
+
+    pub fn new(
+        device: &Device,
+        pipelines: &mut PipelineCache,
+        target: SceneTarget,
+        size: PhysicalSize<u32>,
+        voxel_buffer: &Buffer,
+        light_buffer: &Buffer,
+        grid_buffer: &Buffer,
+    ) -> Result<Self> {
+        let compute_bind_group_layout =
+            layout::bind_group_layout(device, "Path Trace Bind Group Layout", &Self::compute_layout_entries());
+        let display_bind_group_layout =
+            layout::bind_group_layout(device, "Path Trace Display Bind Group Layout", &Self::display_layout_entries());
+
+        let compute_pipeline = create_pathtrace_pipeline(pipelines, &compute_bind_group_layout)?;
+        let display_pipeline = create_pathtrace_display_pipeline(pipelines, target, &display_bind_group_layout)?;
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Path Trace Uniform Buffer"),
+            size: std::mem::size_of::<PathTraceUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let accumulation_buffer = Self::create_accumulation_buffer(device, size);
+        let (compute_bind_group, display_bind_group) = Self::create_bind_groups(
+            device,
+            &compute_bind_group_layout,
+            &display_bind_group_layout,
+            &uniform_buffer,
+            &accumulation_buffer,
+            [voxel_buffer, light_buffer, grid_buffer],
+        );
+
+        Ok(Self {
+            compute_pipeline,
+            display_pipeline,
+            compute_bind_group_layout,
+            display_bind_group_layout,
+            uniform_buffer,
+            accumulation_buffer,
+            compute_bind_group,
+            display_bind_group,
+            voxel_buffer: voxel_buffer.clone(),
+            light_buffer: light_buffer.clone(),
+            grid_buffer: grid_buffer.clone(),
+            size,
+            frame: 0,
+            last_view_proj: Matrix4::zeros(),
+            progressive: None,
+        })
+    }
+
+    fn create_accumulation_buffer(device: &Device, size: PhysicalSize<u32>) -> Buffer {
+        let pixels = (size.width.max(1) * size.height.max(1)) as wgpu::BufferAddress;
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Path Trace Accumulation Buffer"),
+            size: pixels * 16, // vec4<f32> на пиксель
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_groups(
+        device: &Device,
+        compute_layout: &BindGroupLayout,
+        display_layout: &BindGroupLayout,
+        uniform_buffer: &Buffer,
+        accumulation_buffer: &Buffer,
+        [voxel_buffer, light_buffer, grid_buffer]: [&Buffer; 3],
+    ) -> (BindGroup, BindGroup) {
+        fn entry(binding: u32, buffer: &Buffer) -> wgpu::BindGroupEntry<'_> {
+            wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            }
+        }
+        let compute = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Path Trace Bind Group"),
+            layout: compute_layout,
+            entries: &[
+                entry(0, uniform_buffer),
+                entry(1, voxel_buffer),
+                entry(2, light_buffer),
+                entry(3, grid_buffer),
+                entry(4, accumulation_buffer),
+            ],
+        });
+        let display = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Path Trace Display Bind Group"),
+            layout: display_layout,
+            entries: &[entry(0, uniform_buffer), entry(1, accumulation_buffer)],
+        });
+        (compute, display)
+    }
+
+    /// Пересоздаёт буфер накопления под новый размер окна
+    pub fn resize(&mut self, device: &Device, size: PhysicalSize<u32>) {
+        self.size = size;
+        self.accumulation_buffer = Self::create_accumulation_buffer(device, size);
+        let (compute, display) = Self::create_bind_groups(
+            device,
+            &self.compute_bind_group_layout,
+            &self.display_bind_group_layout,
+            &self.uniform_buffer,
+            &self.accumulation_buffer,
+            [&self.voxel_buffer, &self.light_buffer, &self.grid_buffer],
+        );
+        self.compute_bind_group = compute;
+        self.display_bind_group = display;
+        self.frame = 0;
+    }
+
+    /// Сбрасывает накопление (камера сдвинулась или сцена изменилась)
+    pub fn reset(&mut self) {
+        self.frame = 0;
+    }
+
+    /// Сколько сэмплов на пиксель уже накоплено
+    pub fn samples(&self) -> u32 {
+        self.frame * SAMPLES_PER_FRAME
+    }
+
+    /// Добавляет кадр сэмплов в буфер накопления
+    pub fn dispatch(&mut self, queue: &Queue, encoder: &mut CommandEncoder, camera: &Camera, sun: &Sun) {
+        self.update(queue, camera, sun);
+        self.record(encoder);
+    }
+
+    /// Готовит следующий кадр сэмплов: сбрасывает накопление, если камера сдвинулась, и пишет uniform
+    pub fn update(&mut self, queue: &Queue, camera: &Camera, sun: &Sun) {
+        let view_proj = camera.view_proj_matrix();
+        if view_proj != self.last_view_proj {
+            self.last_view_proj = view_proj;
+            self.frame = 0;
+        }
+
+        let uniform = PathTraceUniform {
+            inv_view_proj: view_proj.try_inverse().unwrap_or_else(Matrix4::identity).into(),
+            camera_position: camera.position.to_homogeneous().into(),
+            sun_direction: sun.direction.push(0.0).into(),
+            size: [self.size.width, self.size.height],
+            frame: self.frame,
+            samples_per_frame: SAMPLES_PER_FRAME,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+        self.frame += 1;
+    }
+
+    /// Записывает трассировку кадра, подготовленного `update`
+    pub fn record(&self, encoder: &mut CommandEncoder) {
+        // Первый кадр после сброса начинает с пустого буфера
+        if self.frame == 1 {
+            encoder.clear_buffer(&self.accumulation_buffer, 0, None);
+        }
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Path Trace Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            self.size.width.div_ceil(WORKGROUP_SIZE),
+            self.size.height.div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    /// Выводит накопленное изображение в текущий проход
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_pipeline(&self.display_pipeline);
+        render_pass.set_bind_group(0, &self.display_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Сохраняет PNG, если прогрессивный рендер набрал нужное число сэмплов.
+    /// `samples` – число сэмплов MSAA, с которым собран пайплайн вывода.
+    pub fn save_if_done(&mut self, device: &Device, queue: &Queue, post: &PostProcess, samples: u32) {
+        let Some(target) = &self.progressive else { return };
+        if self.samples() < target.samples {
+            return;
+        }
+        match self.save_png(device, queue, post, samples, &target.path) {
+            Ok(()) => tracing::info!("Progressive render saved to {} ({} spp)", target.path.display(), self.samples()),
+            Err(err) => tracing::error!("{}", err),
+        }
+        self.progressive = None;
+    }
+
+    /// Сохраняет накопленное изображение в PNG, пропустив через постобработку, как на экране
+    pub fn save_png(&self, device: &Device, queue: &Queue, post: &PostProcess, samples: u32, path: &Path) -> Result<()> {
+        let image = post.export(device, queue, self.size, samples, |render_pass| self.draw(render_pass))?;
+        write_png(path, self.size, &image).map_err(|source| CuborumError::Image { path: path.into(), source })
+    }
+}
+
+fn write_png(path: &Path, size: PhysicalSize<u32>, image: &[u8]) -> std::result::Result<(), png::EncodingError> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, size.width.max(1), size.height.max(1));
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image)
+}
@@ -0,0 +1,144 @@
+use nalgebra::{Point3, Vector3};
+
+use crate::renderer::voxel::VoxelGrid;
+
+/// Результат трассировки луча по сетке
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RayHit {
+    pub cell: [usize; 3],             // Первая занятая ячейка
+    pub previous: Option<[usize; 3]>, // Пустая ячейка перед ней (куда можно поставить воксель)
+}
+
+fn to_cell(grid: &VoxelGrid, pos: [i32; 3]) -> Option<[usize; 3]> {
+    let size = grid.size as i32;
+    if pos.iter().any(|&c| c < 0 || c >= size) {
+        return None;
+    }
+    Some(pos.map(|c| c as usize))
+}
+
+/// Трассирует луч в мировых координатах по сетке (DDA, Amanatides–Woo)
+pub fn raycast(grid: &VoxelGrid, origin: Point3<f32>, direction: Vector3<f32>, max_distance: f32) -> Option<RayHit> {
+    let direction = direction.try_normalize(f32::EPSILON)?;
+    let voxel_size = grid.voxel_size();
+    let grid_origin = grid.origin();
+
+    // Переходим в координаты сетки: один воксель = 1
+    let start = Vector3::new(
+        (origin.x - grid_origin[0]) / voxel_size,
+        (origin.y - grid_origin[1]) / voxel_size,
+        (origin.z - grid_origin[2]) / voxel_size,
+    );
+    let max_t = max_distance / voxel_size;
+
+    let mut cell = [start.x.floor() as i32, start.y.floor() as i32, start.z.floor() as i32];
+    let mut step = [0i32; 3];
+    let mut t_max = [f32::INFINITY; 3];
+    let mut t_delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        let d = direction[axis];
+        if d > 0.0 {
+            step[axis] = 1;
+            t_delta[axis] = 1.0 / d;
+            t_max[axis] = (cell[axis] as f32 + 1.0 - start[axis]) / d;
+        } else if d < 0.0 {
+            step[axis] = -1;
+            t_delta[axis] = -1.0 / d;
+            t_max[axis] = (start[axis] - cell[axis] as f32) / -d;
+        }
+    }
+
+    let mut previous = None;
+    let mut t = 0.0;
+    while t <= max_t {
+        if grid.is_solid(cell) {
+            return to_cell(grid, cell).map(|cell| RayHit { cell, previous });
+        }
+        previous = to_cell(grid, cell);
+
+        let axis = if t_max[0] < t_max[1] {
+            if t_max[0] < t_max[2] { 0 } else { 2 }
+        } else if t_max[1] < t_max[2] {
+            1
+        } else {
+            2
+        };
+        cell[axis] += step[axis];
+        t = t_max[axis];
+        t_max[axis] += t_delta[axis];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::voxel::Voxel;
+
+    /// Сетка 4³ (воксель 0.5) с занятыми ячейками `cells`
+    fn grid_with(cells: &[[usize; 3]]) -> VoxelGrid {
+        let mut grid = VoxelGrid::new(4);
+        for &[x, y, z] in cells {
+            grid.set(x, y, z, Voxel::new(1, 255, 255, 255, 255));
+        }
+        grid
+    }
+
+    fn center(grid: &VoxelGrid, cell: [usize; 3]) -> Point3<f32> {
+        Point3::from(std::array::from_fn(|i| grid.origin()[i] + (cell[i] as f32 + 0.5) * grid.voxel_size()))
+    }
+
+    /// Нормаль грани, через которую луч вошёл в ячейку
+    fn normal(hit: &RayHit) -> [i32; 3] {
+        let previous = hit.previous.expect("previous cell");
+        std::array::from_fn(|i| previous[i] as i32 - hit.cell[i] as i32)
+    }
+
+    #[test]
+    fn axis_aligned_ray_stops_at_first_solid_cell() {
+        let grid = grid_with(&[[2, 1, 1], [3, 1, 1]]);
+        let hit = raycast(&grid, center(&grid, [0, 1, 1]), Vector3::x(), 10.0).unwrap();
+        assert_eq!(hit, RayHit { cell: [2, 1, 1], previous: Some([1, 1, 1]) });
+        assert_eq!(normal(&hit), [-1, 0, 0]);
+    }
+
+    #[test]
+    fn diagonal_ray_reports_the_face_it_entered_through() {
+        // Стена x = 3: луч входит в неё через грань -X
+        let wall: Vec<[usize; 3]> = (0..16).map(|i| [3, i / 4, i % 4]).collect();
+        let grid = grid_with(&wall);
+        let hit = raycast(&grid, center(&grid, [0, 0, 0]), Vector3::new(1.0, 0.5, 0.25), 10.0).unwrap();
+        assert_eq!(hit.cell, [3, 1, 1]);
+        assert_eq!(normal(&hit), [-1, 0, 0]);
+
+        // Пол y = 0: сверху наискось луч входит через грань +Y
+        let floor: Vec<[usize; 3]> = (0..16).map(|i| [i / 4, 0, i % 4]).collect();
+        let grid = grid_with(&floor);
+        let hit = raycast(&grid, center(&grid, [0, 3, 0]), Vector3::new(0.3, -1.0, 0.1), 10.0).unwrap();
+        assert_eq!(hit, RayHit { cell: [1, 0, 0], previous: Some([1, 1, 0]) });
+        assert_eq!(normal(&hit), [0, 1, 0]);
+    }
+
+    #[test]
+    fn hits_beyond_max_distance_are_missed() {
+        let grid = grid_with(&[[3, 1, 1]]);
+        let origin = center(&grid, [0, 1, 1]);
+        // До грани ячейки 1.25 в мировых единицах
+        assert_eq!(raycast(&grid, origin, Vector3::x(), 1.0), None);
+        assert!(raycast(&grid, origin, Vector3::x(), 1.5).is_some());
+        assert_eq!(raycast(&grid, origin, -Vector3::x(), 10.0), None);
+    }
+
+    #[test]
+    fn ray_from_outside_enters_the_grid() {
+        let grid = grid_with(&[[1, 1, 1]]);
+        let origin = Point3::new(-3.0, -0.25, -0.25);
+        let hit = raycast(&grid, origin, Vector3::x(), 10.0).unwrap();
+        assert_eq!(hit, RayHit { cell: [1, 1, 1], previous: Some([0, 1, 1]) });
+
+        // Занятая ячейка на самой границе: ставить воксель некуда
+        let grid = grid_with(&[[0, 1, 1]]);
+        let hit = raycast(&grid, origin, Vector3::x(), 10.0).unwrap();
+        assert_eq!(hit, RayHit { cell: [0, 1, 1], previous: None });
+    }
+}
@@ -0,0 +1,264 @@
+use wgpu::{
+    BindGroupLayout, BlendState, ColorTargetState, CompareFunction, ComputePipeline, DepthStencilState, Device,
+    MultisampleState, PrimitiveState, RenderPipeline, ShaderModule, TextureFormat,
+    VertexBufferLayout,
+};
+
+use crate::error::{CuborumError, Result};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::texture::DEPTH_FORMAT;
+
+/// Точка входа шейдера вместе с define-ами, с которыми он собирается
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderEntry {
+    pub shader: Shader,
+    pub entry_point: &'static str,
+    pub defines: ShaderDefines,
+}
+
+impl ShaderEntry {
+    pub fn new(shader: Shader, entry_point: &'static str, defines: ShaderDefines) -> Self {
+        Self { shader, entry_point, defines }
+    }
+}
+
+/// Декларативное описание рендерного пайплайна. Оно же ключ в `PipelineCache`:
+/// одинаковые описания дают один и тот же пайплайн, раскладки сравниваются по идентичности.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineBuilder {
+    pub label: &'static str,
+    pub vertex: ShaderEntry,
+    pub fragment: Option<ShaderEntry>,
+    pub bind_group_layouts: Vec<BindGroupLayout>,
+    pub vertex_buffers: Vec<VertexBufferLayout<'static>>,
+    pub primitive: PrimitiveState,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub depth_bias: wgpu::DepthBiasState, // Попадает в `depth_stencil` при сборке
+    pub multisample: MultisampleState,
+    pub targets: Vec<Option<ColorTargetState>>,
+}
+
+impl PipelineBuilder {
+    /// Список треугольников без отсечения граней, без глубины и цветовых целей, один сэмпл
+    pub fn new(label: &'static str, vertex: ShaderEntry) -> Self {
+        Self {
+            label,
+            vertex,
+            fragment: None,
+            bind_group_layouts: Vec::new(),
+            vertex_buffers: Vec::new(),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            depth_bias: wgpu::DepthBiasState::default(),
+            multisample: MultisampleState::default(),
+            targets: Vec::new(),
+        }
+    }
+
+    pub fn fragment(mut self, fragment: ShaderEntry) -> Self {
+        self.fragment = Some(fragment);
+        self
+    }
+
+    /// Раскладки групп в порядке `@group(n)`
+    pub fn bind_groups(mut self, layouts: &[&BindGroupLayout]) -> Self {
+        self.bind_group_layouts = layouts.iter().map(|&layout| layout.clone()).collect();
+        self
+    }
+
+    pub fn vertex_buffer(mut self, layout: VertexBufferLayout<'static>) -> Self {
+        self.vertex_buffers.push(layout);
+        self
+    }
+
+    pub fn cull_back_faces(mut self) -> Self {
+        self.primitive.cull_mode = Some(wgpu::Face::Back);
+        self
+    }
+
+    /// Буфер глубины формата `DEPTH_FORMAT`
+    pub fn depth(mut self, compare: CompareFunction, write: bool) -> Self {
+        self.depth_stencil = Some(DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: write,
+            depth_compare: compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        });
+        self
+    }
+
+    /// Смещение глубины; без `depth` сборка вернёт ошибку
+    pub fn depth_bias(mut self, constant: i32, slope_scale: f32) -> Self {
+        self.depth_bias = wgpu::DepthBiasState { constant, slope_scale, clamp: 0.0 };
+        self
+    }
+
+    pub fn color_target(mut self, format: TextureFormat, blend: Option<BlendState>) -> Self {
+        self.targets.push(Some(ColorTargetState {
+            format,
+            blend,
+            write_mask: wgpu::ColorWrites::ALL,
+        }));
+        self
+    }
+
+    /// Число сэмплов MSAA
+    pub fn samples(mut self, count: u32) -> Self {
+        self.multisample.count = count;
+        self
+    }
+
+    /// Собирает пайплайн или берёт уже собранный по тому же описанию
+    pub fn build(&self, cache: &mut PipelineCache) -> Result<RenderPipeline> {
+        cache.render(self)
+    }
+
+    /// Ошибки описания, которые wgpu не поймает сам
+    pub(super) fn check(&self) -> Result<()> {
+        if self.depth_stencil.is_none() && self.depth_bias != wgpu::DepthBiasState::default() {
+            return Err(CuborumError::Pipeline {
+                label: self.label.to_string(),
+                message: "depth_bias() requires depth()".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn uses(&self, shader: Shader) -> bool {
+        self.vertex.shader == shader || self.fragment.as_ref().is_some_and(|f| f.shader == shader)
+    }
+
+    pub(super) fn create(&self, device: &Device, vertex: &ShaderModule, fragment: Option<&ShaderModule>) -> RenderPipeline {
+        let bind_group_layouts: Vec<&BindGroupLayout> = self.bind_group_layouts.iter().collect();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(self.label),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(self.label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: vertex,
+                entry_point: Some(self.vertex.entry_point),
+                compilation_options: Default::default(),
+                buffers: &self.vertex_buffers,
+            },
+            primitive: self.primitive,
+            depth_stencil: self.depth_stencil.clone().map(|depth| DepthStencilState { bias: self.depth_bias, ..depth }),
+            multisample: self.multisample,
+            fragment: self.fragment.as_ref().zip(fragment).map(|(entry, module)| wgpu::FragmentState {
+                module,
+                entry_point: Some(entry.entry_point),
+                compilation_options: Default::default(),
+                targets: &self.targets,
+            }),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+/// Описание compute-пайплайна, ключ в `PipelineCache`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ComputePipelineBuilder {
+    pub label: &'static str,
+    pub shader: ShaderEntry,
+    pub bind_group_layouts: Vec<BindGroupLayout>,
+}
+
+impl ComputePipelineBuilder {
+    pub fn new(label: &'static str, shader: ShaderEntry) -> Self {
+        Self {
+            label,
+            shader,
+            bind_group_layouts: Vec::new(),
+        }
+    }
+
+    /// Раскладки групп в порядке `@group(n)`
+    pub fn bind_groups(mut self, layouts: &[&BindGroupLayout]) -> Self {
+        self.bind_group_layouts = layouts.iter().map(|&layout| layout.clone()).collect();
+        self
+    }
+
+    pub fn build(&self, cache: &mut PipelineCache) -> Result<ComputePipeline> {
+        cache.compute(self)
+    }
+
+    pub fn uses(&self, shader: Shader) -> bool {
+        self.shader.shader == shader
+    }
+
+    pub(super) fn create(&self, device: &Device, module: &ShaderModule) -> ComputePipeline {
+        let bind_group_layouts: Vec<&BindGroupLayout> = self.bind_group_layouts.iter().collect();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(self.label),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(self.label),
+            layout: Some(&layout),
+            module,
+            entry_point: Some(self.shader.entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    fn fullscreen() -> PipelineBuilder {
+        PipelineBuilder::new("Test", ShaderEntry::new(Shader::RayMarch, "vs_main", ShaderDefines::new()))
+            .fragment(ShaderEntry::new(Shader::RayMarch, "fs_main", ShaderDefines::new()))
+            .color_target(TextureFormat::Bgra8UnormSrgb, Some(BlendState::REPLACE))
+    }
+
+    fn key_hash(builder: &PipelineBuilder) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        builder.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_descriptions_share_a_key() {
+        assert_eq!(fullscreen(), fullscreen());
+        assert_eq!(key_hash(&fullscreen()), key_hash(&fullscreen()));
+    }
+
+    #[test]
+    fn settings_change_the_key() {
+        let base = fullscreen();
+        assert_ne!(base, fullscreen().samples(4));
+        assert_ne!(base, fullscreen().depth(CompareFunction::Less, true));
+        assert_ne!(base, fullscreen().cull_back_faces());
+        let defines = ShaderDefines::new().flag("FAST", true);
+        assert_ne!(base, fullscreen().fragment(ShaderEntry::new(Shader::RayMarch, "fs_main", defines)));
+    }
+
+    #[test]
+    fn depth_bias_needs_depth() {
+        let depth = || fullscreen().depth(CompareFunction::LessEqual, true);
+        assert_eq!(fullscreen().depth_bias(2, 2.0).depth(CompareFunction::LessEqual, true), depth().depth_bias(2, 2.0));
+        assert!(depth().depth_bias(2, 2.0).check().is_ok());
+        assert!(matches!(fullscreen().depth_bias(2, 2.0).check(), Err(CuborumError::Pipeline { .. })));
+    }
+
+    #[test]
+    fn builder_reports_used_shaders() {
+        let voxel = PipelineBuilder::new("Voxel", ShaderEntry::new(Shader::VoxelVertex, "main", ShaderDefines::new()))
+            .fragment(ShaderEntry::new(Shader::VoxelFragment, "main", ShaderDefines::new()));
+        assert!(voxel.uses(Shader::VoxelFragment));
+        assert!(!voxel.uses(Shader::Shadow));
+    }
+}
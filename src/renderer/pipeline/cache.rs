@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use wgpu::{ComputePipeline, Device, RenderPipeline, ShaderModule};
+
+use crate::error::{CuborumError, Result};
+use crate::renderer::pipeline::builder::{ComputePipelineBuilder, PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::common::{load_shader, ShaderDefines};
+use crate::renderer::shaders::{Shader, ShaderSource};
+
+/// Собранные шейдерные модули и пайплайны по их описаниям.
+/// Повторная сборка с тем же описанием ничего не пересобирает; после правки шейдера
+/// `invalidate` выкидывает только то, что от него зависит. Метка пайплайна – его место:
+/// новое описание с той же меткой (другое MSAA, формат) вытесняет старое.
+pub struct PipelineCache {
+    device: Device,
+    pub shaders: ShaderSource,
+    modules: HashMap<(Shader, ShaderDefines), ShaderModule>,
+    render: HashMap<PipelineBuilder, RenderPipeline>,
+    compute: HashMap<ComputePipelineBuilder, ComputePipeline>,
+}
+
+impl PipelineCache {
+    pub fn new(device: &Device, shaders: ShaderSource) -> Self {
+        Self {
+            device: device.clone(),
+            shaders,
+            modules: HashMap::new(),
+            render: HashMap::new(),
+            compute: HashMap::new(),
+        }
+    }
+
+    fn module(&mut self, entry: &ShaderEntry) -> Result<ShaderModule> {
+        let key = (entry.shader, entry.defines.clone());
+        if let Some(module) = self.modules.get(&key) {
+            return Ok(module.clone());
+        }
+        let module = load_shader(&self.device, &self.shaders, entry.shader, &entry.defines)?;
+        self.modules.insert(key, module.clone());
+        Ok(module)
+    }
+
+    pub fn render(&mut self, builder: &PipelineBuilder) -> Result<RenderPipeline> {
+        if let Some(pipeline) = self.render.get(builder) {
+            return Ok(pipeline.clone());
+        }
+        builder.check()?;
+        let vertex = self.module(&builder.vertex)?;
+        let fragment = builder.fragment.as_ref().map(|entry| self.module(entry)).transpose()?;
+
+        let device = self.device.clone();
+        let pipeline = validated(&device, builder.label, || builder.create(&device, &vertex, fragment.as_ref()))?;
+        replace(&mut self.render, builder, pipeline.clone(), |key| key.label == builder.label);
+        Ok(pipeline)
+    }
+
+    pub fn compute(&mut self, builder: &ComputePipelineBuilder) -> Result<ComputePipeline> {
+        if let Some(pipeline) = self.compute.get(builder) {
+            return Ok(pipeline.clone());
+        }
+        let module = self.module(&builder.shader)?;
+
+        let device = self.device.clone();
+        let pipeline = validated(&device, builder.label, || builder.create(&device, &module))?;
+        replace(&mut self.compute, builder, pipeline.clone(), |key| key.label == builder.label);
+        Ok(pipeline)
+    }
+
+    /// Забывает модули и пайплайны, собранные из изменившихся шейдеров
+    pub fn invalidate(&mut self, changed: &[Shader]) {
+        self.modules.retain(|(shader, _), _| !changed.contains(shader));
+        self.render.retain(|builder, _| !changed.iter().any(|&shader| builder.uses(shader)));
+        self.compute.retain(|builder, _| !changed.iter().any(|&shader| builder.uses(shader)));
+    }
+}
+
+/// Кладёт `value` по `key`, выкидывая записи, которые `same_slot` считает предыдущими версиями
+fn replace<K: Clone + Eq + Hash, V>(map: &mut HashMap<K, V>, key: &K, value: V, same_slot: impl Fn(&K) -> bool) {
+    map.retain(|old, _| !same_slot(old));
+    map.insert(key.clone(), value);
+}
+
+/// Создаёт пайплайн, перехватывая ошибки валидации (например, несовпадение привязок с шейдером)
+fn validated<T>(device: &Device, label: &str, create: impl FnOnce() -> T) -> Result<T> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = create();
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(err) => Err(CuborumError::Pipeline {
+            label: label.to_string(),
+            message: err.to_string(),
+        }),
+        None => {
+            tracing::debug!("Created pipeline {}", label);
+            Ok(pipeline)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_description_replaces_the_old_one_with_the_same_label() {
+        // Ключ – (метка, число сэмплов), как у PipelineBuilder с разным MSAA
+        let mut map = HashMap::new();
+        for key in [("Voxel", 1), ("Shadow", 1), ("Voxel", 4), ("Voxel", 1)] {
+            replace(&mut map, &key, key.1, |old| old.0 == key.0);
+        }
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&("Voxel", 1)), Some(&1));
+        assert!(!map.contains_key(&("Voxel", 4)));
+    }
+}
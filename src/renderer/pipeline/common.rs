@@ -1,12 +1,350 @@
+use std::collections::{BTreeMap, HashSet};
+
 use wgpu::Device;
 use wgpu::ShaderModule;
-use std::fs;
 
-/// Загружает WGSL-шейдер из файла
-pub fn load_shader(device: &Device, path: &str, label: &str) -> ShaderModule {
-    let shader_src = fs::read_to_string(path).expect(&format!("Failed to read shader file: {}", path));
-    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+use crate::error::{CuborumError, Result};
+use crate::renderer::shaders::{Shader, ShaderSource};
+use crate::renderer::camera::CameraUniform;
+use crate::renderer::voxel::{GridUniform, Voxel, EMISSIVE_STRENGTH, VOXEL_EMISSIVE, VOXEL_EMPTY, VOXEL_SOLID};
+
+/// Имя сгенерированного include с общими структурами (`#include <types>`)
+pub const TYPES_INCLUDE: &str = "types";
+
+/// Загружает WGSL-шейдер (вшитый или из каталога) и прогоняет его через препроцессор.
+/// Ошибки компиляции перехватываются через error scope и возвращаются вместо паники в обработчике wgpu.
+pub fn load_shader(device: &Device, shaders: &ShaderSource, shader: Shader, defines: &ShaderDefines) -> Result<ShaderModule> {
+    let label = shader.label();
+    let shader_src = preprocess(label, &shaders.read(shader)?, shaders, defines)?;
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some(label),
         source: wgpu::ShaderSource::Wgsl(shader_src.into()),
-    })
+    });
+    // На нативных бэкендах scope готов сразу, ожидание не блокирует
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(err) => Err(CuborumError::ShaderCompile {
+            label: label.to_string(),
+            message: err.to_string(),
+        }),
+        None => Ok(module),
+    }
+}
+
+/// Define-ы конкретного пайплайна: размеры групп, сетки, включение фич
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderDefines {
+    values: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Имя заменяется в тексте шейдера на `value` (WGSL-литерал, например `16u`)
+    pub fn define(mut self, name: &str, value: impl ToString) -> Self {
+        self.values.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Флаг для `#ifdef`, включается только если `enabled`
+    pub fn flag(mut self, name: &str, enabled: bool) -> Self {
+        if enabled {
+            self.values.insert(name.to_string(), String::new());
+        }
+        self
+    }
+}
+
+/// Раскрывает директивы в исходнике WGSL:
+/// - `#include "file.wgsl"` – файл из `include/` рядом с шейдерами, `#include <types>` – структуры из Rust;
+///   каждый include вставляется один раз;
+/// - `#define NAME value` – замена идентификатора NAME дальше по тексту;
+/// - `#ifdef NAME` / `#ifndef NAME` / `#else` / `#endif`.
+pub fn preprocess(label: &str, source: &str, shaders: &ShaderSource, defines: &ShaderDefines) -> Result<String> {
+    let mut preprocessor = Preprocessor {
+        label,
+        shaders,
+        defines: defines.values.clone(),
+        included: HashSet::new(),
+        output: String::with_capacity(source.len()),
+    };
+    preprocessor.process(label, source)?;
+    Ok(preprocessor.output)
+}
+
+/// Ветка `#ifdef`: активна ли сейчас и был ли уже `#else`
+struct Condition {
+    active: bool,
+    has_else: bool,
+}
+
+struct Preprocessor<'a> {
+    label: &'a str,
+    shaders: &'a ShaderSource,
+    defines: BTreeMap<String, String>,
+    included: HashSet<String>,
+    output: String,
+}
+
+impl Preprocessor<'_> {
+    fn process(&mut self, file: &str, source: &str) -> Result<()> {
+        let label = self.label;
+        let mut conditions: Vec<Condition> = Vec::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let error = |message: String| CuborumError::ShaderPreprocess {
+                label: label.to_string(),
+                message: format!("{}:{}: {}", file, number + 1, message),
+            };
+            let active = conditions.iter().all(|c| c.active);
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    self.substitute(line);
+                    self.output.push('\n');
+                }
+                continue;
+            };
+            let (name, argument) = directive.split_once(char::is_whitespace).unwrap_or((directive, ""));
+            let argument = argument.trim();
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    let defined = self.defines.contains_key(argument);
+                    conditions.push(Condition { active: defined == (name == "ifdef"), has_else: false });
+                }
+                "else" => match conditions.last_mut() {
+                    Some(condition) if !condition.has_else => {
+                        condition.active = !condition.active;
+                        condition.has_else = true;
+                    }
+                    Some(_) => return Err(error("duplicate #else".to_string())),
+                    None => return Err(error("#else without #ifdef".to_string())),
+                },
+                "endif" => {
+                    if conditions.pop().is_none() {
+                        return Err(error("#endif without #ifdef".to_string()));
+                    }
+                }
+                _ if !active => {}
+                "define" => {
+                    let (define, value) = argument.split_once(char::is_whitespace).unwrap_or((argument, ""));
+                    if !is_identifier(define) {
+                        return Err(error(format!("invalid #define name `{}`", define)));
+                    }
+                    self.defines.insert(define.to_string(), value.trim().to_string());
+                }
+                "include" => {
+                    let include = parse_include(argument).ok_or_else(|| error(format!("invalid #include {}", argument)))?;
+                    if self.included.insert(include.name().to_string()) {
+                        let text = match include {
+                            Include::Generated(TYPES_INCLUDE) => generated_types(),
+                            Include::Generated(other) => return Err(error(format!("unknown generated include <{}>", other))),
+                            Include::File(name) => self.shaders.read_include(name)?.into_owned(),
+                        };
+                        self.process(include.name(), &text)?;
+                    }
+                }
+                _ => return Err(error(format!("unknown directive #{}", name))),
+            }
+        }
+
+        if !conditions.is_empty() {
+            return Err(CuborumError::ShaderPreprocess {
+                label: self.label.to_string(),
+                message: format!("{}: unterminated #ifdef", file),
+            });
+        }
+        Ok(())
+    }
+
+    /// Дописывает строку в вывод, заменяя идентификаторы-define на их значения
+    fn substitute(&mut self, line: &str) {
+        let mut rest = line;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            // Хвост числового литерала (`16u`, `1e5`) не идентификатор
+            let in_number = rest[..start].ends_with(|c: char| c.is_ascii_alphanumeric());
+            let length = rest[start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len() - start);
+            let word = &rest[start..start + length];
+            self.output.push_str(&rest[..start]);
+            match self.defines.get(word) {
+                Some(value) if !in_number && !value.is_empty() => self.output.push_str(value),
+                _ => self.output.push_str(word),
+            }
+            rest = &rest[start + length..];
+        }
+        self.output.push_str(rest);
+    }
+}
+
+enum Include<'a> {
+    File(&'a str),
+    Generated(&'a str),
+}
+
+impl<'a> Include<'a> {
+    fn name(&self) -> &'a str {
+        match self {
+            Include::File(name) | Include::Generated(name) => name,
+        }
+    }
+}
+
+fn parse_include(argument: &str) -> Option<Include<'_>> {
+    if let Some(name) = argument.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+        return Some(Include::File(name));
+    }
+    argument.strip_prefix('<').and_then(|a| a.strip_suffix('>')).map(Include::Generated)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Rust-тип поля и его имя в WGSL
+pub trait WgslType {
+    const WGSL: &'static str;
+}
+
+macro_rules! wgsl_types {
+    ($($rust:ty => $wgsl:literal),* $(,)?) => {
+        $(impl WgslType for $rust {
+            const WGSL: &'static str = $wgsl;
+        })*
+    };
+}
+
+wgsl_types! {
+    u32 => "u32",
+    i32 => "i32",
+    f32 => "f32",
+    [u32; 3] => "vec3<u32>",
+    [f32; 3] => "vec3<f32>",
+    [f32; 4] => "vec4<f32>",
+    [[f32; 4]; 4] => "mat4x4<f32>",
+}
+
+/// Поле `WgslStruct`: имя и тип WGSL
+#[derive(Clone, Copy, Debug)]
+pub struct WgslField {
+    pub name: &'static str,
+    pub ty: &'static str,
+}
+
+/// Rust-структура `#[repr(C)]`, которая описывается в WGSL через `#include <types>`.
+/// Реализуется только макросом `wgsl_struct!`, чтобы поля брались из самого объявления.
+pub trait WgslStruct {
+    const NAME: &'static str;
+    /// Все поля в порядке объявления. Поля с `_` в начале – явный паддинг: в WGSL их нет,
+    /// там структура выравнивается сама.
+    const FIELDS: &'static [WgslField];
+
+    fn wgsl() -> String {
+        let fields: String = Self::FIELDS
+            .iter()
+            .filter(|field| !field.name.starts_with('_'))
+            .map(|field| format!("    {}: {},\n", field.name, field.ty))
+            .collect();
+        format!("struct {} {{\n{}}};\n", Self::NAME, fields)
+    }
+}
+
+/// Объявляет структуру и её `WgslStruct` с именем `$wgsl` по тем же полям.
+/// Сумма размеров полей должна равняться размеру типа, иначе не соберётся: неявный паддинг
+/// `repr(C)` в WGSL не повторить, его нужно объявить полем `_padding`.
+macro_rules! wgsl_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $rust:ident as $wgsl:literal {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $rust {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::renderer::pipeline::common::WgslStruct for $rust {
+            const NAME: &'static str = $wgsl;
+            const FIELDS: &'static [$crate::renderer::pipeline::common::WgslField] = &[$(
+                $crate::renderer::pipeline::common::WgslField {
+                    name: stringify!($field),
+                    ty: <$ty as $crate::renderer::pipeline::common::WgslType>::WGSL,
+                }
+            ),*];
+        }
+
+        const _: () = assert!(
+            0 $(+ std::mem::size_of::<$ty>())* == std::mem::size_of::<$rust>(),
+            concat!("fields of ", stringify!($rust), " do not cover it, declare the padding explicitly"),
+        );
+    };
+}
+pub(crate) use wgsl_struct;
+
+/// Общие для шейдеров типы и константы, взятые из Rust
+pub fn generated_types() -> String {
+    let mut types = String::new();
+    for (name, value) in [("VOXEL_EMPTY", VOXEL_EMPTY), ("VOXEL_SOLID", VOXEL_SOLID), ("VOXEL_EMISSIVE", VOXEL_EMISSIVE)] {
+        types += &format!("const {}: u32 = {}u;\n", name, value);
+    }
+    types += &format!("const EMISSIVE_STRENGTH: f32 = {:?};\n", EMISSIVE_STRENGTH);
+    types += &Voxel::wgsl();
+    types += &Voxel::unpack_color_wgsl();
+    types += &GridUniform::wgsl();
+    types += &CameraUniform::wgsl();
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, defines: &ShaderDefines) -> Result<String> {
+        preprocess("test", source, &ShaderSource::Embedded, defines)
+    }
+
+    #[test]
+    fn defines_replace_whole_identifiers() {
+        let defines = ShaderDefines::new().define("SIZE", "16u");
+        let output = run("#define HALF 8u\nlet a = SIZE * HALF + SIZE_X + 1e5;", &defines).unwrap();
+        assert_eq!(output, "let a = 16u * 8u + SIZE_X + 1e5;\n");
+    }
+
+    #[test]
+    fn ifdef_selects_branches() {
+        let source = "#ifdef PCF\npcf\n#ifndef FAST\nslow\n#endif\n#else\nhard\n#endif\nend";
+        let on = ShaderDefines::new().flag("PCF", true);
+        assert_eq!(run(source, &on).unwrap(), "pcf\nslow\nend\n");
+        let off = ShaderDefines::new().flag("PCF", false);
+        assert_eq!(run(source, &off).unwrap(), "hard\nend\n");
+    }
+
+    #[test]
+    fn includes_are_expanded_once() {
+        let output = run("#include <types>\n#include \"voxel.wgsl\"\n#include \"voxel.wgsl\"", &ShaderDefines::new()).unwrap();
+        assert_eq!(output.matches("struct Voxel {").count(), 1);
+        assert_eq!(output.matches("fn unpack_color").count(), 1);
+    }
+
+    #[test]
+    fn malformed_directives_are_errors() {
+        for source in ["#endif", "#ifdef A\n", "#ifdef A\n#else\n#else\n#endif", "#pragma once", "#include <missing>"] {
+            assert!(matches!(run(source, &ShaderDefines::new()), Err(CuborumError::ShaderPreprocess { .. })), "{}", source);
+        }
+    }
+
+    #[test]
+    fn generated_structs_follow_rust_fields() {
+        assert_eq!(Voxel::wgsl(), "struct Voxel {\n    voxel_type: u32,\n    color: u32,\n};\n");
+        assert!(!GridUniform::wgsl().contains("_padding"));
+        assert!(generated_types().contains(&format!("const VOXEL_EMISSIVE: u32 = {}u;", VOXEL_EMISSIVE)));
+        assert!(Voxel::unpack_color_wgsl().contains("vec4<f32>(f32((packed >> 24u) & 0xFFu), f32((packed >> 16u) & 0xFFu),"));
+    }
 }
@@ -1,22 +1,27 @@
-use wgpu::{BindGroupLayout, ComputePipeline, Device};
-use crate::renderer::pipeline::common::load_shader;
+use wgpu::{BindGroupLayout, ComputePipeline};
+use crate::error::Result;
+use crate::renderer::pipeline::builder::{ComputePipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
 
-/// Создаёт Compute Pipeline для работы с вокселями
-pub fn create_compute_pipeline(device: &Device, voxel_bind_group_layout: &BindGroupLayout) -> ComputePipeline {
-    let compute_shader = load_shader(device, "shaders/compute_voxel.wgsl", "Compute Shader");
+/// Размер рабочей группы compute_voxel.wgsl по каждой оси
+pub const VOXEL_WORKGROUP_SIZE: u32 = 8;
 
-    let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Compute Pipeline Layout"),
-        bind_group_layouts: &[voxel_bind_group_layout], // ✅ Используем биндинг вокселей
-        push_constant_ranges: &[],
-    });
+/// Define-ы compute_voxel.wgsl для сетки размера `grid_size`
+pub fn compute_defines(grid_size: u32) -> ShaderDefines {
+    ShaderDefines::new()
+        .define("WORKGROUP_SIZE", format!("{}u", VOXEL_WORKGROUP_SIZE))
+        .define("GRID_SIZE", format!("{}u", grid_size))
+}
 
-    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("Compute Pipeline"),
-        layout: Some(&compute_pipeline_layout),
-        module: &compute_shader,
-        entry_point: Some("main"),
-        compilation_options: Default::default(),
-        cache: None
-    })
+/// Создаёт Compute Pipeline для работы с вокселями сетки размера `grid_size`
+pub fn create_compute_pipeline(
+    cache: &mut PipelineCache,
+    voxel_bind_group_layout: &BindGroupLayout,
+    grid_size: u32,
+) -> Result<ComputePipeline> {
+    ComputePipelineBuilder::new("Compute Pipeline", ShaderEntry::new(Shader::ComputeVoxel, "main", compute_defines(grid_size)))
+        .bind_groups(&[voxel_bind_group_layout]) // ✅ Используем биндинг вокселей
+        .build(cache)
 }
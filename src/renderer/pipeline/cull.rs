@@ -0,0 +1,19 @@
+use wgpu::{BindGroupLayout, ComputePipeline};
+use crate::error::Result;
+use crate::renderer::culling::WORKGROUP_SIZE;
+use crate::renderer::pipeline::builder::{ComputePipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+
+/// Define-ы cull.wgsl
+pub fn cull_defines() -> ShaderDefines {
+    ShaderDefines::new().define("WORKGROUP_SIZE", format!("{}u", WORKGROUP_SIZE))
+}
+
+/// Создаёт Compute Pipeline для отсечения чанков по пирамиде видимости
+pub fn create_cull_pipeline(cache: &mut PipelineCache, cull_bind_group_layout: &BindGroupLayout) -> Result<ComputePipeline> {
+    ComputePipelineBuilder::new("Cull Pipeline", ShaderEntry::new(Shader::Cull, "main", cull_defines()))
+        .bind_groups(&[cull_bind_group_layout])
+        .build(cache)
+}
@@ -0,0 +1,89 @@
+use wgpu::{BindGroupLayout, BindGroupLayoutEntry, BindingType, BufferBindingType, Device, ShaderStages};
+
+/// Создаёт раскладку группы из готовых записей
+pub fn bind_group_layout(device: &Device, label: &str, entries: &[BindGroupLayoutEntry]) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries,
+    })
+}
+
+fn buffer(binding: u32, visibility: ShaderStages, ty: BufferBindingType) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// `var<uniform>`
+pub fn uniform(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    buffer(binding, visibility, BufferBindingType::Uniform)
+}
+
+/// `var<storage, read>` или `var<storage, read_write>`
+pub fn storage(binding: u32, visibility: ShaderStages, read_only: bool) -> BindGroupLayoutEntry {
+    buffer(binding, visibility, BufferBindingType::Storage { read_only })
+}
+
+/// Storage-массив из элементов `T`: буфер короче одного элемента не пройдёт валидацию привязки
+pub fn storage_array<T>(binding: u32, visibility: ShaderStages, read_only: bool) -> BindGroupLayoutEntry {
+    let mut entry = storage(binding, visibility, read_only);
+    if let BindingType::Buffer { min_binding_size, .. } = &mut entry.ty {
+        *min_binding_size = wgpu::BufferSize::new(size_of::<T>() as u64);
+    }
+    entry
+}
+
+/// `texture_depth_2d_array`
+pub fn depth_texture_array(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Depth,
+            view_dimension: wgpu::TextureViewDimension::D2Array,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+/// `sampler_comparison`
+pub fn comparison_sampler(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+        count: None,
+    }
+}
+
+/// `texture_2d<f32>` с фильтрацией
+pub fn texture(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+/// `sampler` с фильтрацией
+pub fn sampler(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }
+}
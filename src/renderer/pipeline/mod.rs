@@ -1,25 +1,47 @@
+pub mod builder;
+pub mod cache;
 pub mod compute;
+pub mod cull;
 pub mod voxel;
 pub mod common;
+pub mod layout;
+pub mod pathtrace;
+pub mod post;
+pub mod raymarch;
+pub mod shadow;
+#[cfg(test)]
+mod validation;
 
 use wgpu::BindGroupLayout;
-use wgpu::Device;
-use wgpu::TextureFormat;
-use wgpu::ComputePipeline;
 use wgpu::RenderPipeline;
 
+use crate::error::Result;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::texture::SceneTarget;
+
 pub use compute::create_compute_pipeline;
+pub use cull::create_cull_pipeline;
+pub use pathtrace::{create_pathtrace_display_pipeline, create_pathtrace_pipeline};
+pub use post::{create_bloom_pipelines, create_blit_pipeline, create_fxaa_pipeline, create_tonemap_pipeline};
+pub use raymarch::create_raymarch_pipeline;
+pub use shadow::create_shadow_pipeline;
 pub use voxel::create_voxel_pipeline;
 
-/// Создаёт все пайплайны (compute и voxel)
+/// Создаёт рендерные пайплайны сетки (voxel и shadow)
 pub fn create_pipelines(
-    device: &Device,
-    surface_format: TextureFormat,
-    voxel_compute_bind_group_layout: &BindGroupLayout,
+    cache: &mut PipelineCache,
+    target: SceneTarget,
     voxel_render_bind_group_layout: &BindGroupLayout,
     camera_bind_group_layout: &BindGroupLayout,
-) -> (ComputePipeline, RenderPipeline) {
-    let compute_pipeline = create_compute_pipeline(device, voxel_compute_bind_group_layout);
-    let voxel_pipeline = create_voxel_pipeline(device, surface_format, voxel_render_bind_group_layout, camera_bind_group_layout);
-    (compute_pipeline, voxel_pipeline)
+    shadow_bind_group_layout: &BindGroupLayout,
+) -> Result<(RenderPipeline, RenderPipeline)> {
+    let voxel_pipeline = create_voxel_pipeline(
+        cache,
+        target,
+        voxel_render_bind_group_layout,
+        camera_bind_group_layout,
+        shadow_bind_group_layout,
+    )?;
+    let shadow_pipeline = create_shadow_pipeline(cache, camera_bind_group_layout)?;
+    Ok((voxel_pipeline, shadow_pipeline))
 }
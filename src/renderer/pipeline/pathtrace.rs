@@ -0,0 +1,37 @@
+use wgpu::{BindGroupLayout, ComputePipeline, RenderPipeline};
+use crate::error::Result;
+use crate::renderer::pathtrace::WORKGROUP_SIZE;
+use crate::renderer::pipeline::builder::{ComputePipelineBuilder, PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::texture::SceneTarget;
+
+/// Define-ы pathtrace.wgsl
+pub fn pathtrace_defines() -> ShaderDefines {
+    ShaderDefines::new().define("WORKGROUP_SIZE", format!("{}u", WORKGROUP_SIZE))
+}
+
+/// Создаёт Compute Pipeline трассировщика путей (накопление сэмплов в буфер)
+pub fn create_pathtrace_pipeline(cache: &mut PipelineCache, pathtrace_bind_group_layout: &BindGroupLayout) -> Result<ComputePipeline> {
+    ComputePipelineBuilder::new("Path Trace Pipeline", ShaderEntry::new(Shader::PathTrace, "main", pathtrace_defines()))
+        .bind_groups(&[pathtrace_bind_group_layout])
+        .build(cache)
+}
+
+/// Создаёт полноэкранный пайплайн, выводящий среднее накопленных сэмплов
+pub fn create_pathtrace_display_pipeline(
+    cache: &mut PipelineCache,
+    target: SceneTarget,
+    display_bind_group_layout: &BindGroupLayout,
+) -> Result<RenderPipeline> {
+    let entry = |name| ShaderEntry::new(Shader::PathTraceDisplay, name, ShaderDefines::new());
+    PipelineBuilder::new("Path Trace Display Pipeline", entry("vs_main"))
+        .fragment(entry("fs_main"))
+        .bind_groups(&[display_bind_group_layout])
+        // Проход общий с растром, поэтому формат глубины и число сэмплов должны совпадать; глубину не трогаем
+        .depth(wgpu::CompareFunction::Always, false)
+        .color_target(target.format, Some(wgpu::BlendState::REPLACE))
+        .samples(target.samples)
+        .build(cache)
+}
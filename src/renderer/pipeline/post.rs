@@ -0,0 +1,57 @@
+use wgpu::{BindGroupLayout, RenderPipeline, TextureFormat};
+use crate::error::Result;
+use crate::renderer::pipeline::builder::{PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::texture::{HDR_FORMAT, LDR_FORMAT};
+
+/// Define-ы blit.wgsl: кодировать sRGB в шейдере, если формат цели не делает этого сам
+pub fn blit_defines(format: TextureFormat) -> ShaderDefines {
+    ShaderDefines::new().flag("ENCODE_SRGB", !format.is_srgb())
+}
+
+/// Полноэкранный проход постобработки: вершинный шейдер из post.wgsl, без глубины
+fn fullscreen(
+    label: &'static str,
+    shader: Shader,
+    entry_point: &'static str,
+    defines: ShaderDefines,
+    layout: &BindGroupLayout,
+    format: TextureFormat,
+) -> PipelineBuilder {
+    PipelineBuilder::new(label, ShaderEntry::new(shader, "vs_main", defines.clone()))
+        .fragment(ShaderEntry::new(shader, entry_point, defines))
+        .bind_groups(&[layout])
+        .color_target(format, Some(wgpu::BlendState::REPLACE))
+}
+
+/// Пайплайны bloom: выделение ярких пикселей и два прохода размытия
+pub fn create_bloom_pipelines(cache: &mut PipelineCache, layout: &BindGroupLayout) -> Result<[RenderPipeline; 3]> {
+    let bloom = |label, entry_point| fullscreen(label, Shader::Bloom, entry_point, ShaderDefines::new(), layout, HDR_FORMAT);
+    Ok([
+        bloom("Bloom Threshold Pipeline", "fs_threshold").build(cache)?,
+        bloom("Bloom Blur Horizontal Pipeline", "fs_blur_horizontal").build(cache)?,
+        bloom("Bloom Blur Vertical Pipeline", "fs_blur_vertical").build(cache)?,
+    ])
+}
+
+/// Экспозиция и тонмаппинг HDR-кадра в LDR
+pub fn create_tonemap_pipeline(cache: &mut PipelineCache, tonemap_layout: &BindGroupLayout) -> Result<RenderPipeline> {
+    fullscreen("Tonemap Pipeline", Shader::Tonemap, "fs_main", ShaderDefines::new(), tonemap_layout, LDR_FORMAT).build(cache)
+}
+
+pub fn create_fxaa_pipeline(cache: &mut PipelineCache, layout: &BindGroupLayout) -> Result<RenderPipeline> {
+    fullscreen("FXAA Pipeline", Shader::Fxaa, "fs_main", ShaderDefines::new(), layout, LDR_FORMAT).build(cache)
+}
+
+/// Вывод готового кадра в цель формата `output_format`. Метки у вывода на экран и в файл
+/// разные, иначе в кэше они вытесняли бы друг друга.
+pub fn create_blit_pipeline(
+    cache: &mut PipelineCache,
+    label: &'static str,
+    layout: &BindGroupLayout,
+    output_format: TextureFormat,
+) -> Result<RenderPipeline> {
+    fullscreen(label, Shader::Blit, "fs_main", blit_defines(output_format), layout, output_format).build(cache)
+}
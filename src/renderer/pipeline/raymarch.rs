@@ -0,0 +1,26 @@
+use wgpu::{BindGroupLayout, RenderPipeline};
+use crate::error::Result;
+use crate::renderer::pipeline::builder::{PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::texture::SceneTarget;
+
+/// Создаёт полноэкранный пайплайн, который трассирует воксели лучами (DDA) вместо растеризации
+pub fn create_raymarch_pipeline(
+    cache: &mut PipelineCache,
+    target: SceneTarget,
+    raymarch_bind_group_layout: &BindGroupLayout,
+    voxel_render_bind_group_layout: &BindGroupLayout,
+) -> Result<RenderPipeline> {
+    let entry = |name| ShaderEntry::new(Shader::RayMarch, name, ShaderDefines::new());
+    // Полноэкранный треугольник из vertex_index, вершинных буферов нет
+    PipelineBuilder::new("Ray March Pipeline", entry("vs_main"))
+        .fragment(entry("fs_main"))
+        .bind_groups(&[raymarch_bind_group_layout, voxel_render_bind_group_layout])
+        // Глубина пишется из шейдера (frag_depth), чтобы поверх можно было рисовать растром
+        .depth(wgpu::CompareFunction::Always, true)
+        .color_target(target.format, Some(wgpu::BlendState::REPLACE))
+        .samples(target.samples)
+        .build(cache)
+}
@@ -0,0 +1,19 @@
+use wgpu::{BindGroupLayout, RenderPipeline};
+use crate::error::Result;
+use crate::renderer::pipeline::builder::{PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::vertex::Vertex;
+
+/// Создаёт пайплайн теневого прохода: рендер мешей вокселей в глубину с точки зрения солнца
+pub fn create_shadow_pipeline(cache: &mut PipelineCache, light_bind_group_layout: &BindGroupLayout) -> Result<RenderPipeline> {
+    // Только глубина, фрагментного шейдера нет
+    PipelineBuilder::new("Shadow Pipeline", ShaderEntry::new(Shader::Shadow, "main", ShaderDefines::new()))
+        .bind_groups(&[light_bind_group_layout]) // Матрица света в том же формате, что и камера
+        .vertex_buffer(Vertex::layout())
+        .cull_back_faces()
+        .depth(wgpu::CompareFunction::LessEqual, true)
+        .depth_bias(2, 2.0) // Смещение против «теневых угрей» (shadow acne)
+        .build(cache)
+}
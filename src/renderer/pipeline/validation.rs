@@ -0,0 +1,270 @@
+//! Проверка WGSL без GPU: разбор и валидация naga, сверка раскладок структур
+//! с Rust-типами и биндингов шейдеров с раскладками групп.
+
+use std::mem::{offset_of, size_of};
+
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use naga::{AddressSpace, Module, ShaderStage, StorageAccess, TypeInner};
+use wgpu::{BindGroupLayoutEntry, BindingType, BufferBindingType, ShaderStages};
+
+use crate::renderer::camera::CameraUniform;
+use crate::renderer::chunk::{ChunkInfo, DrawIndexedIndirectArgs};
+use crate::renderer::culling::{CullUniform, GpuCulling};
+use crate::renderer::pathtrace::{PathTraceUniform, PathTracer};
+use crate::renderer::pipeline::common::{preprocess, ShaderDefines};
+use crate::renderer::pipeline::compute::compute_defines;
+use crate::renderer::pipeline::cull::cull_defines;
+use crate::renderer::pipeline::pathtrace::pathtrace_defines;
+use crate::renderer::pipeline::post::blit_defines;
+use crate::renderer::post::{PostProcess, PostUniform};
+use crate::renderer::pipeline::voxel::voxel_fragment_defines;
+use crate::renderer::raymarch::{RayMarchUniform, RayMarcher};
+use crate::renderer::shaders::{Shader, ShaderSource};
+use crate::renderer::shadow::ShadowUniform;
+use crate::renderer::state::init::{
+    camera_layout_entries, shadow_layout_entries, voxel_compute_layout_entries, voxel_render_layout_entries,
+};
+use crate::renderer::vertex::Vertex;
+use crate::renderer::voxel::{GridUniform, Voxel};
+
+/// Define-ы, с которыми шейдер собирает его пайплайн
+fn defines(shader: Shader) -> ShaderDefines {
+    match shader {
+        Shader::ComputeVoxel => compute_defines(16),
+        Shader::Cull => cull_defines(),
+        Shader::PathTrace => pathtrace_defines(),
+        Shader::VoxelFragment => voxel_fragment_defines(),
+        // Формат без sRGB-варианта включает ветку ENCODE_SRGB
+        Shader::Blit => blit_defines(wgpu::TextureFormat::Rgb10a2Unorm),
+        _ => ShaderDefines::new(),
+    }
+}
+
+/// Препроцессирует, разбирает и валидирует вшитый шейдер
+fn compile(shader: Shader) -> (Module, ModuleInfo) {
+    let shaders = ShaderSource::Embedded;
+    let source = preprocess(shader.label(), &shaders.read(shader).unwrap(), &shaders, &defines(shader)).unwrap();
+    let module = naga::front::wgsl::parse_str(&source)
+        .unwrap_or_else(|err| panic!("{}:\n{}", shader.file_name(), err.emit_to_string(&source)));
+    let info = Validator::new(ValidationFlags::all(), Capabilities::default())
+        .validate(&module)
+        .unwrap_or_else(|err| panic!("{}:\n{}", shader.file_name(), err.emit_to_string(&source)));
+    (module, info)
+}
+
+/// Сверяет WGSL-структуру `name` с Rust-типом: размер и смещения перечисленных полей.
+/// Совпадение размера со `span` значит и совпадение шага в массивах.
+fn check_struct(module: &Module, name: &str, rust_size: usize, fields: &[(&str, usize)]) {
+    let (_, ty) = module
+        .types
+        .iter()
+        .find(|(_, ty)| ty.name.as_deref() == Some(name))
+        .unwrap_or_else(|| panic!("no struct {} in shader", name));
+    let TypeInner::Struct { members, span } = &ty.inner else { panic!("{} is not a struct", name) };
+
+    assert_eq!(*span as usize, rust_size, "size of {}", name);
+    for (field, offset) in fields {
+        let member = members
+            .iter()
+            .find(|m| m.name.as_deref() == Some(field))
+            .unwrap_or_else(|| panic!("no field {}.{} in shader", name, field));
+        assert_eq!(member.offset as usize, *offset, "offset of {}.{}", name, field);
+    }
+}
+
+macro_rules! check_layout {
+    ($module:expr, $wgsl:literal, $rust:ty { $($field:ident),* $(,)? }) => {
+        check_struct(&$module, $wgsl, size_of::<$rust>(), &[$((stringify!($field), offset_of!($rust, $field))),*])
+    };
+}
+
+#[test]
+fn every_shader_validates() {
+    for shader in Shader::ALL {
+        compile(shader);
+    }
+}
+
+#[test]
+fn struct_layouts_match_rust_types() {
+    let (compute, _) = compile(Shader::ComputeVoxel);
+    check_layout!(compute, "Voxel", Voxel { voxel_type, color });
+    check_layout!(compute, "Grid", GridUniform { origin, voxel_size, size });
+
+    let (vertex, _) = compile(Shader::VoxelVertex);
+    check_layout!(vertex, "Camera", CameraUniform { view_proj, view_depth });
+
+    let (fragment, _) = compile(Shader::VoxelFragment);
+    check_layout!(fragment, "Shadow", ShadowUniform { light_view_proj, splits, sun_direction });
+
+    let (raymarch, _) = compile(Shader::RayMarch);
+    check_layout!(raymarch, "RayMarch", RayMarchUniform { inv_view_proj, view_proj, camera_position, sun_direction, viewport });
+
+    for shader in [Shader::PathTrace, Shader::PathTraceDisplay] {
+        let (pathtrace, _) = compile(shader);
+        check_layout!(pathtrace, "PathTrace", PathTraceUniform {
+            inv_view_proj,
+            camera_position,
+            sun_direction,
+            size,
+            frame,
+            samples_per_frame,
+        });
+    }
+
+    for shader in [Shader::Bloom, Shader::Tonemap, Shader::Fxaa, Shader::Blit] {
+        let (post, _) = compile(shader);
+        check_layout!(post, "Post", PostUniform { exposure, tonemap, bloom_threshold, bloom_strength });
+    }
+
+    let (cull, _) = compile(Shader::Cull);
+    check_layout!(cull, "Cull", CullUniform { planes, camera_position, draw_count, lod_distances });
+    check_layout!(cull, "ChunkInfo", ChunkInfo { aabb_min, first_index, aabb_max, index_count, base_vertex, lod });
+    check_layout!(cull, "DrawIndexedIndirectArgs", DrawIndexedIndirectArgs {
+        index_count,
+        instance_count,
+        first_index,
+        base_vertex,
+        first_instance,
+    });
+}
+
+#[test]
+fn vertex_inputs_match_vertex_layout() {
+    let offsets = [
+        offset_of!(Vertex, position),
+        offset_of!(Vertex, color),
+        offset_of!(Vertex, ao),
+        offset_of!(Vertex, normal),
+        offset_of!(Vertex, emission),
+    ];
+    for (attribute, offset) in Vertex::ATTRIBS.iter().zip(offsets) {
+        assert_eq!(attribute.offset as usize, offset, "offset of location {}", attribute.shader_location);
+    }
+
+    for shader in [Shader::VoxelVertex, Shader::Shadow] {
+        let (module, _) = compile(shader);
+        let entry = &module.entry_points[0];
+        // Входы – либо аргументы с @location, либо поля структуры-аргумента
+        let mut inputs = Vec::new();
+        for argument in &entry.function.arguments {
+            match (&argument.binding, &module.types[argument.ty].inner) {
+                (Some(binding), _) => inputs.push((binding.clone(), argument.ty)),
+                (None, TypeInner::Struct { members, .. }) => {
+                    inputs.extend(members.iter().map(|m| (m.binding.clone().unwrap(), m.ty)));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        for (binding, ty) in inputs {
+            let naga::Binding::Location { location, .. } = binding else { continue };
+            let attribute = Vertex::ATTRIBS
+                .iter()
+                .find(|a| a.shader_location == location)
+                .unwrap_or_else(|| panic!("{}: no vertex attribute for location {}", shader.file_name(), location));
+            let components = match attribute.format {
+                wgpu::VertexFormat::Float32 => 1,
+                wgpu::VertexFormat::Float32x2 => 2,
+                wgpu::VertexFormat::Float32x3 => 3,
+                wgpu::VertexFormat::Float32x4 => 4,
+                format => panic!("unexpected vertex format {:?}", format),
+            };
+            let (size, scalar) = match module.types[ty].inner {
+                TypeInner::Scalar(scalar) => (1, scalar),
+                TypeInner::Vector { size, scalar } => (size as u32, scalar),
+                ref other => panic!("{}: location {} has type {:?}", shader.file_name(), location, other),
+            };
+            assert_eq!((size, scalar), (components, naga::Scalar::F32), "{}: location {}", shader.file_name(), location);
+        }
+    }
+}
+
+fn stage_flags(stage: ShaderStage) -> ShaderStages {
+    match stage {
+        ShaderStage::Vertex => ShaderStages::VERTEX,
+        ShaderStage::Fragment => ShaderStages::FRAGMENT,
+        ShaderStage::Compute => ShaderStages::COMPUTE,
+    }
+}
+
+/// Подходит ли запись раскладки под глобальную переменную шейдера
+fn binding_matches(module: &Module, global: &naga::GlobalVariable, entry: &BindGroupLayoutEntry) -> bool {
+    match (global.space, &module.types[global.ty].inner, entry.ty) {
+        (AddressSpace::Uniform, _, BindingType::Buffer { ty: BufferBindingType::Uniform, .. }) => true,
+        (AddressSpace::Storage { access }, _, BindingType::Buffer { ty: BufferBindingType::Storage { read_only }, .. }) => {
+            read_only != access.contains(StorageAccess::STORE)
+        }
+        (AddressSpace::Handle, TypeInner::Sampler { comparison }, BindingType::Sampler(sampler)) => {
+            *comparison == (sampler == wgpu::SamplerBindingType::Comparison)
+        }
+        (AddressSpace::Handle, TypeInner::Image { dim, arrayed, class }, BindingType::Texture { sample_type, view_dimension, multisampled }) => {
+            let dimension = match (dim, arrayed) {
+                (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                (naga::ImageDimension::D3, false) => wgpu::TextureViewDimension::D3,
+                (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                _ => return false,
+            };
+            let class_matches = match (class, sample_type) {
+                (naga::ImageClass::Depth { multi }, wgpu::TextureSampleType::Depth) => *multi == multisampled,
+                (naga::ImageClass::Sampled { kind: naga::ScalarKind::Float, multi }, wgpu::TextureSampleType::Float { .. }) => {
+                    *multi == multisampled
+                }
+                _ => false,
+            };
+            dimension == view_dimension && class_matches
+        }
+        _ => false,
+    }
+}
+
+/// Каждый ресурс, который использует точка входа, есть в раскладке своей группы,
+/// совпадает по типу и виден на стадии этой точки входа
+fn check_bindings(shader: Shader, groups: &[&[BindGroupLayoutEntry]]) {
+    let (module, info) = compile(shader);
+    for (index, entry_point) in module.entry_points.iter().enumerate() {
+        let usage = info.get_entry_point(index);
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else { continue };
+            if usage[handle].is_empty() {
+                continue;
+            }
+            let name = global.name.as_deref().unwrap_or("?");
+            let layout = groups
+                .get(binding.group as usize)
+                .unwrap_or_else(|| panic!("{}: `{}` uses missing group {}", shader.file_name(), name, binding.group));
+            let entry = layout
+                .iter()
+                .find(|e| e.binding == binding.binding)
+                .unwrap_or_else(|| panic!("{}: `{}` has no layout entry {:?}", shader.file_name(), name, binding));
+            assert!(binding_matches(&module, global, entry), "{}: `{}` does not match {:?}", shader.file_name(), name, entry.ty);
+            assert!(
+                entry.visibility.contains(stage_flags(entry_point.stage)),
+                "{}: `{}` is not visible to {:?}",
+                shader.file_name(),
+                name,
+                entry_point.stage
+            );
+        }
+    }
+}
+
+#[test]
+fn bind_group_layouts_match_shader_bindings() {
+    let (camera, shadow) = (camera_layout_entries(), shadow_layout_entries());
+    let (voxel_compute, voxel_render) = (voxel_compute_layout_entries(), voxel_render_layout_entries());
+
+    check_bindings(Shader::ComputeVoxel, &[&voxel_compute]);
+    check_bindings(Shader::VoxelVertex, &[&camera, &voxel_render, &shadow]);
+    check_bindings(Shader::VoxelFragment, &[&camera, &voxel_render, &shadow]);
+    check_bindings(Shader::Shadow, &[&camera]);
+    check_bindings(Shader::RayMarch, &[&RayMarcher::layout_entries(), &voxel_render]);
+    check_bindings(Shader::PathTrace, &[&PathTracer::compute_layout_entries()]);
+    check_bindings(Shader::PathTraceDisplay, &[&PathTracer::display_layout_entries()]);
+    check_bindings(Shader::Cull, &[&GpuCulling::layout_entries()]);
+    for shader in [Shader::Bloom, Shader::Fxaa, Shader::Blit] {
+        check_bindings(shader, &[&PostProcess::layout_entries()]);
+    }
+    check_bindings(Shader::Tonemap, &[&PostProcess::tonemap_layout_entries()]);
+}
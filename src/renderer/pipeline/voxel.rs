@@ -1,57 +1,35 @@
-use wgpu::{BindGroupLayout, Device, RenderPipeline, TextureFormat};
-use crate::renderer::pipeline::common::load_shader;
+use wgpu::{BindGroupLayout, RenderPipeline};
+use crate::error::Result;
+use crate::renderer::shadow::CASCADE_COUNT;
+use crate::renderer::pipeline::builder::{PipelineBuilder, ShaderEntry};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::common::ShaderDefines;
+use crate::renderer::shaders::Shader;
+use crate::renderer::texture::SceneTarget;
+use crate::renderer::vertex::Vertex;
+
+/// Define-ы voxel_fragment.wgsl
+pub fn voxel_fragment_defines() -> ShaderDefines {
+    ShaderDefines::new()
+        .define("CASCADE_COUNT", format!("{}u", CASCADE_COUNT))
+        .flag("SHADOW_PCF", true)
+}
 
 /// Создаёт рендерный пайплайн для вокселей
 pub fn create_voxel_pipeline(
-    device: &Device,
-    surface_format: TextureFormat,
+    cache: &mut PipelineCache,
+    target: SceneTarget,
     voxel_render_bind_group_layout: &BindGroupLayout,
     camera_bind_group_layout: &BindGroupLayout,
-) -> RenderPipeline {
-    let vertex_shader = load_shader(device, "shaders/voxel_vertex.wgsl", "Voxel Vertex Shader");
-    let fragment_shader = load_shader(device, "shaders/voxel_fragment.wgsl", "Voxel Fragment Shader");
-
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Voxel Render Pipeline Layout"),
-        bind_group_layouts: &[camera_bind_group_layout, voxel_render_bind_group_layout], // ✅ Камера и воксели
-        push_constant_ranges: &[],
-    });
-
-    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Voxel Render Pipeline"),
-        layout: Some(&pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &vertex_shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            buffers: &[], // Пока без вершинного буфера
-        },
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::PointList, // 🔥 Начнём с точек
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: None, // Воксели – это точки, куллинг не нужен
-            polygon_mode: wgpu::PolygonMode::Fill,
-            unclipped_depth: false,
-            conservative: false,
-        },
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &fragment_shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: surface_format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        multiview: None,
-        cache: None,
-    })
+    shadow_bind_group_layout: &BindGroupLayout,
+) -> Result<RenderPipeline> {
+    PipelineBuilder::new("Voxel Render Pipeline", ShaderEntry::new(Shader::VoxelVertex, "main", ShaderDefines::new()))
+        .fragment(ShaderEntry::new(Shader::VoxelFragment, "main", voxel_fragment_defines()))
+        .bind_groups(&[camera_bind_group_layout, voxel_render_bind_group_layout, shadow_bind_group_layout])
+        .vertex_buffer(Vertex::layout())
+        .cull_back_faces()
+        .depth(wgpu::CompareFunction::Less, true)
+        .color_target(target.format, Some(wgpu::BlendState::REPLACE))
+        .samples(target.samples)
+        .build(cache)
 }
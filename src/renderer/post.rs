@@ -0,0 +1,373 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Queue, RenderPipeline, Sampler, TextureFormat, TextureView};
+use winit::dpi::PhysicalSize;
+
+use crate::error::{CuborumError, Result};
+use crate::renderer::graph::{RenderGraph, ResourceId, TextureDesc, TransientPool};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_bloom_pipelines, create_blit_pipeline, create_fxaa_pipeline, create_tonemap_pipeline, layout};
+use crate::renderer::texture::{MainTargets, HDR_FORMAT, LDR_FORMAT};
+
+/// Порог яркости сцены (до экспозиции), выше которого пиксель светится
+pub const BLOOM_THRESHOLD: f32 = 1.0;
+pub const BLOOM_STRENGTH: f32 = 0.6;
+/// Множитель экспозиции за одно нажатие (полступени)
+pub const EXPOSURE_STEP: f32 = std::f32::consts::SQRT_2;
+const EXPOSURE_RANGE: (f32, f32) = (1.0 / 16.0, 16.0);
+
+/// Кривая сжатия HDR в [0, 1] (значения совпадают с константами в tonemap.wgsl)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tonemap {
+    Off = 0, // Только обрезка
+    Aces = 1,
+    Filmic = 2, // Hable
+}
+
+impl Tonemap {
+    pub fn next(self) -> Self {
+        match self {
+            Tonemap::Off => Tonemap::Aces,
+            Tonemap::Aces => Tonemap::Filmic,
+            Tonemap::Filmic => Tonemap::Off,
+        }
+    }
+}
+
+/// Этапы постобработки, переключаемые на лету
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostSettings {
+    pub exposure: f32,
+    pub tonemap: Tonemap,
+    pub bloom: bool,
+    pub fxaa: bool,
+}
+
+impl Default for PostSettings {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            tonemap: Tonemap::Aces,
+            bloom: true,
+            fxaa: true,
+        }
+    }
+}
+
+impl PostSettings {
+    /// Умножает экспозицию на `factor`, не выходя за разумные пределы
+    pub fn scale_exposure(&mut self, factor: f32) {
+        self.exposure = (self.exposure * factor).clamp(EXPOSURE_RANGE.0, EXPOSURE_RANGE.1);
+    }
+}
+
+/// Uniform постобработки (совпадает с `Post` в include/post.wgsl)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct PostUniform {
+    pub exposure: f32,
+    pub tonemap: u32,
+    pub bloom_threshold: f32,
+    pub bloom_strength: f32,
+}
+
+impl PostUniform {
+    pub fn new(settings: &PostSettings) -> Self {
+        Self {
+            exposure: settings.exposure,
+            tonemap: settings.tonemap as u32,
+            bloom_threshold: BLOOM_THRESHOLD,
+            bloom_strength: if settings.bloom { BLOOM_STRENGTH } else { 0.0 },
+        }
+    }
+}
+
+/// Цепочка постобработки HDR-кадра: bloom, экспозиция и тонмаппинг, FXAA, вывод на экран
+pub struct PostProcess {
+    pub settings: PostSettings,
+    pub bind_group_layout: BindGroupLayout,
+    pub tonemap_bind_group_layout: BindGroupLayout,
+    pub sampler: Sampler,
+    pub uniform_buffer: Buffer,
+    pub no_bloom: TextureView, // Чёрная 1×1 вместо bloom, когда он выключен
+    pub output_format: TextureFormat,
+    pub bloom_pipelines: [RenderPipeline; 3], // Порог, размытие по горизонтали и по вертикали
+    pub tonemap_pipeline: RenderPipeline,
+    pub fxaa_pipeline: RenderPipeline,
+    pub blit_pipeline: RenderPipeline,
+    pub export_pipeline: RenderPipeline, // Blit в LDR_FORMAT для сохранения кадра в файл
+}
+
+impl PostProcess {
+    /// Раскладка прохода: исходная текстура, сэмплер, uniform
+    pub fn layout_entries() -> [wgpu::BindGroupLayoutEntry; 3] {
+        let fragment = wgpu::ShaderStages::FRAGMENT;
+        [layout::texture(0, fragment), layout::sampler(1, fragment), layout::uniform(2, fragment)]
+    }
+
+    /// Раскладка тонмаппинга: то же и текстура bloom
+    pub fn tonemap_layout_entries() -> [wgpu::BindGroupLayoutEntry; 4] {
+        let [source, sampler, uniform] = Self::layout_entries();
+        [source, sampler, uniform, layout::texture(3, wgpu::ShaderStages::FRAGMENT)]
+    }
+
+    /// `output_format` – формат вида поверхности, в который выводится кадр
+    pub fn new(device: &Device, pipelines: &mut PipelineCache, output_format: TextureFormat) -> Result<Self> {
+        let bind_group_layout = layout::bind_group_layout(device, "Post Bind Group Layout", &Self::layout_entries());
+        let tonemap_bind_group_layout =
+            layout::bind_group_layout(device, "Tonemap Bind Group Layout", &Self::tonemap_layout_entries());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let settings = PostSettings::default();
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Uniform Buffer"),
+            size: std::mem::size_of::<PostUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Новые текстуры wgpu заполняет нулями
+        let no_bloom = TextureDesc::target(PhysicalSize::new(1, 1), HDR_FORMAT).sampled().create(device, "No Bloom Texture");
+
+        Ok(Self {
+            settings,
+            bloom_pipelines: create_bloom_pipelines(pipelines, &bind_group_layout)?,
+            tonemap_pipeline: create_tonemap_pipeline(pipelines, &tonemap_bind_group_layout)?,
+            fxaa_pipeline: create_fxaa_pipeline(pipelines, &bind_group_layout)?,
+            blit_pipeline: create_blit_pipeline(pipelines, "Blit Pipeline", &bind_group_layout, output_format)?,
+            export_pipeline: create_blit_pipeline(pipelines, "Export Blit Pipeline", &bind_group_layout, LDR_FORMAT)?,
+            bind_group_layout,
+            tonemap_bind_group_layout,
+            sampler,
+            uniform_buffer,
+            no_bloom,
+            output_format,
+        })
+    }
+
+    pub fn update(&self, queue: &Queue) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&PostUniform::new(&self.settings)));
+    }
+
+    /// Добавляет в граф проходы от HDR-кадра `hdr` до поверхности `surface`.
+    /// Выключенные этапы в граф не попадают.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        device: &'a Device,
+        size: PhysicalSize<u32>,
+        hdr: ResourceId,
+        surface: ResourceId,
+    ) {
+        self.add_chain(graph, device, size, hdr, surface, &self.blit_pipeline);
+    }
+
+    /// Рисует кадр сцены (`draw` в основном проходе с `samples` сэмплами), пропускает его через ту же цепочку,
+    /// что и на экране, и возвращает RGBA8 в sRGB размера `size`
+    pub fn export(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        size: PhysicalSize<u32>,
+        samples: u32,
+        draw: impl FnOnce(&mut wgpu::RenderPass<'_>),
+    ) -> Result<Vec<u8>> {
+        self.update(queue); // Без окна update больше никто не зовёт
+        let output = TextureDesc {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            ..TextureDesc::target(size, LDR_FORMAT)
+        };
+        let texture = output.create_texture(device, "Export Texture");
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut graph = RenderGraph::new();
+        let hdr = graph.transient("hdr", TextureDesc::target(size, HDR_FORMAT).sampled());
+        let main = MainTargets::new(&mut graph, size, samples, hdr);
+        let image = graph.import_texture("image", &view);
+        graph.pass("Scene").writes(&main.ids()).record(move |encoder, resources| {
+            let mut render_pass = main.begin(encoder, resources);
+            draw(&mut render_pass);
+        });
+        self.add_chain(&mut graph, device, size, hdr, image, &self.export_pipeline);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        graph.execute(device, &mut encoder, &mut TransientPool::default())?;
+        read_texture(device, queue, encoder, &texture)
+    }
+
+    /// Цепочка от `hdr` до `output`; последний проход рисует `blit` (его формат – формат `output`)
+    fn add_chain<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        device: &'a Device,
+        size: PhysicalSize<u32>,
+        hdr: ResourceId,
+        surface: ResourceId,
+        blit: &'a RenderPipeline,
+    ) {
+        let mut tonemap_reads = vec![hdr];
+        let mut bloom = None;
+        if self.settings.bloom {
+            let half = PhysicalSize::new(size.width.div_ceil(2), size.height.div_ceil(2));
+            let desc = TextureDesc::target(half, HDR_FORMAT).sampled();
+            let bright = graph.transient("bloom bright", desc);
+            let blurred = graph.transient("bloom blurred", desc);
+            let result = graph.transient("bloom", desc);
+            let [threshold, horizontal, vertical] = &self.bloom_pipelines;
+            let stages = [
+                ("Bloom Threshold", threshold, hdr, bright),
+                ("Bloom Blur Horizontal", horizontal, bright, blurred),
+                ("Bloom Blur Vertical", vertical, blurred, result),
+            ];
+            for (name, pipeline, input, output) in stages {
+                graph.pass(name).reads(&[input]).writes(&[output]).record(move |encoder, resources| {
+                    let bind_group = self.bind_group(device, name, &self.bind_group_layout, &[resources.view(input)]);
+                    Self::draw(encoder, name, pipeline, &bind_group, resources.view(output));
+                });
+            }
+            tonemap_reads.push(result);
+            bloom = Some(result);
+        }
+
+        let ldr = TextureDesc::target(size, LDR_FORMAT).sampled();
+        let tonemapped = graph.transient("tonemapped", ldr);
+        graph.pass("Tonemap").reads(&tonemap_reads).writes(&[tonemapped]).record(move |encoder, resources| {
+            let bloom = bloom.map_or(&self.no_bloom, |bloom| resources.view(bloom));
+            let inputs = [resources.view(hdr), bloom];
+            let bind_group = self.bind_group(device, "Tonemap", &self.tonemap_bind_group_layout, &inputs);
+            Self::draw(encoder, "Tonemap", &self.tonemap_pipeline, &bind_group, resources.view(tonemapped));
+        });
+
+        let mut output = tonemapped;
+        if self.settings.fxaa {
+            let antialiased = graph.transient("antialiased", ldr);
+            graph.pass("FXAA").reads(&[tonemapped]).writes(&[antialiased]).record(move |encoder, resources| {
+                let bind_group = self.bind_group(device, "FXAA", &self.bind_group_layout, &[resources.view(tonemapped)]);
+                Self::draw(encoder, "FXAA", &self.fxaa_pipeline, &bind_group, resources.view(antialiased));
+            });
+            output = antialiased;
+        }
+
+        graph.pass("Blit").reads(&[output]).writes(&[surface]).record(move |encoder, resources| {
+            let bind_group = self.bind_group(device, "Blit", &self.bind_group_layout, &[resources.view(output)]);
+            Self::draw(encoder, "Blit", blit, &bind_group, resources.view(surface));
+        });
+    }
+
+    /// Группа прохода с раскладкой `layout`: первая из `inputs` привязывается к binding 0, остальные – к 3, 4…
+    fn bind_group(&self, device: &Device, label: &str, layout: &BindGroupLayout, inputs: &[&TextureView]) -> BindGroup {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(inputs[0]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: self.uniform_buffer.as_entire_binding(),
+            },
+        ];
+        entries.extend(inputs[1..].iter().zip(3..).map(|(&view, binding)| wgpu::BindGroupEntry {
+            binding,
+            resource: wgpu::BindingResource::TextureView(view),
+        }));
+        // Входы – временные текстуры графа, поэтому группа собирается каждый кадр
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    /// Полноэкранный треугольник в `target`
+    fn draw(encoder: &mut CommandEncoder, label: &str, pipeline: &RenderPipeline, bind_group: &BindGroup, target: &TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Дописывает в `encoder` копию RGBA8-текстуры в буфер, выполняет его и возвращает пиксели без выравнивания строк
+fn read_texture(device: &Device, queue: &Queue, mut encoder: CommandEncoder, texture: &wgpu::Texture) -> Result<Vec<u8>> {
+    let (width, height) = (texture.width(), texture.height());
+    let row = width * 4;
+    let padded_row = row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Export Readback Buffer"),
+        size: (padded_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    queue.submit([encoder.finish()]);
+
+    let slice = staging.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).ok();
+    });
+    device.poll(wgpu::Maintain::Wait);
+    // Без ответа (колбэк не вызван) – тоже ошибка отображения
+    let mapped = receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError));
+    mapped.map_err(|source| CuborumError::Readback { label: "exported image", source })?;
+
+    let data = slice.get_mapped_range();
+    let image = data.chunks(padded_row as usize).flat_map(|line| &line[..row as usize]).copied().collect();
+    drop(data);
+    staging.unmap();
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposure_stays_in_range() {
+        let mut settings = PostSettings::default();
+        for _ in 0..20 {
+            settings.scale_exposure(EXPOSURE_STEP);
+        }
+        assert_eq!(settings.exposure, EXPOSURE_RANGE.1);
+        settings.scale_exposure(1.0 / 1000.0);
+        assert_eq!(settings.exposure, EXPOSURE_RANGE.0);
+    }
+
+    #[test]
+    fn disabled_bloom_adds_nothing() {
+        let settings = PostSettings { bloom: false, ..Default::default() };
+        assert_eq!(PostUniform::new(&settings).bloom_strength, 0.0);
+        assert_eq!(PostUniform::new(&PostSettings { tonemap: Tonemap::Filmic, ..settings }).tonemap, 2);
+    }
+}
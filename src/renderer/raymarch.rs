@@ -0,0 +1,96 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPipeline};
+use winit::dpi::PhysicalSize;
+
+use crate::renderer::camera::Camera;
+use crate::error::Result;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_raymarch_pipeline, layout};
+use crate::renderer::shadow::Sun;
+use crate::renderer::texture::SceneTarget;
+
+/// Каким способом рисуются воксели
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderPath {
+    Raster,   // Меши чанков
+    RayMarch, // Полноэкранная трассировка буфера вокселей
+    PathTrace, // Трассировка путей с накоплением (превью)
+}
+
+/// Uniform трассировщика (совпадает с `RayMarch` в raymarch.wgsl)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct RayMarchUniform {
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub view_proj: [[f32; 4]; 4],
+    pub camera_position: [f32; 4],
+    pub sun_direction: [f32; 4],
+    pub viewport: [f32; 4],
+}
+
+impl RayMarchUniform {
+    pub fn new(camera: &Camera, sun: &Sun, size: PhysicalSize<u32>) -> Self {
+        let view_proj = camera.view_proj_matrix();
+        let inv_view_proj = view_proj.try_inverse().unwrap_or_else(nalgebra::Matrix4::identity);
+        Self {
+            inv_view_proj: inv_view_proj.into(),
+            view_proj: view_proj.into(),
+            camera_position: camera.position.to_homogeneous().into(),
+            sun_direction: sun.direction.push(0.0).into(),
+            viewport: [size.width as f32, size.height as f32, 0.0, 0.0],
+        }
+    }
+}
+
+/// Ресурсы полноэкранного трассировщика вокселей
+pub struct RayMarcher {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline: RenderPipeline,
+    pub uniform_buffer: Buffer,
+    pub bind_group: BindGroup,
+}
+
+impl RayMarcher {
+    /// Раскладка: uniform трассировщика (группа 0, воксели – группа 1)
+    pub fn layout_entries() -> [wgpu::BindGroupLayoutEntry; 1] {
+        [layout::uniform(0, wgpu::ShaderStages::FRAGMENT)]
+    }
+
+    pub fn new(
+        device: &Device,
+        pipelines: &mut PipelineCache,
+        target: SceneTarget,
+        voxel_render_bind_group_layout: &BindGroupLayout,
+    ) -> Result<Self> {
+        let bind_group_layout = layout::bind_group_layout(device, "Ray March Bind Group Layout", &Self::layout_entries());
+
+        let pipeline = create_raymarch_pipeline(pipelines, target, &bind_group_layout, voxel_render_bind_group_layout)?;
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray March Uniform Buffer"),
+            size: std::mem::size_of::<RayMarchUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Ray March Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Ok(Self {
+            bind_group_layout,
+            pipeline,
+            uniform_buffer,
+            bind_group,
+        })
+    }
+
+    pub fn update(&self, queue: &Queue, uniform: &RayMarchUniform) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(uniform));
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wgpu::{Device, DeviceLostReason, SurfaceError};
+
+/// Берёт кадр у поверхности. На Lost/Outdated перенастраивает её и пробует ещё раз,
+/// на Timeout и прочих временных сбоях пропускает кадр (None).
+/// OutOfMemory возвращается как ошибка – продолжать рендер нельзя.
+pub fn acquire_frame<T>(
+    mut acquire: impl FnMut() -> Result<T, SurfaceError>,
+    mut reconfigure: impl FnMut(),
+) -> Result<Option<T>, SurfaceError> {
+    for attempt in 0..2 {
+        match acquire() {
+            Ok(frame) => return Ok(Some(frame)),
+            Err(SurfaceError::Lost | SurfaceError::Outdated) if attempt == 0 => {
+                tracing::info!("Surface is lost or outdated, reconfiguring");
+                reconfigure();
+            }
+            Err(SurfaceError::OutOfMemory) => return Err(SurfaceError::OutOfMemory),
+            Err(err) => {
+                tracing::warn!("Skipping frame: {}", err);
+                return Ok(None);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Флаг потери устройства: выставляется колбэком wgpu, проверяется между кадрами
+#[derive(Clone, Default)]
+pub struct DeviceLost(Arc<AtomicBool>);
+
+impl DeviceLost {
+    pub fn watch(device: &Device) -> Self {
+        let lost = Self::default();
+        let flag = lost.0.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            // Destroyed – устройство уничтожили мы сами, восстанавливать нечего
+            if reason == DeviceLostReason::Unknown {
+                tracing::error!("GPU device lost: {}", message);
+                flag.store(true, Ordering::Release);
+            }
+        });
+        lost
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Прогоняет `acquire_frame` по заготовленным ответам поверхности
+    fn acquire(results: Vec<Result<u32, SurfaceError>>) -> (Result<Option<u32>, SurfaceError>, u32) {
+        let mut results = VecDeque::from(results);
+        let mut reconfigured = 0;
+        let frame = acquire_frame(|| results.pop_front().unwrap(), || reconfigured += 1);
+        (frame, reconfigured)
+    }
+
+    #[test]
+    fn lost_and_outdated_surfaces_are_reconfigured() {
+        assert_eq!(acquire(vec![Err(SurfaceError::Lost), Ok(7)]), (Ok(Some(7)), 1));
+        assert_eq!(acquire(vec![Err(SurfaceError::Outdated), Ok(7)]), (Ok(Some(7)), 1));
+        // Не помогло – пропускаем кадр, а не перенастраиваем без конца
+        assert_eq!(acquire(vec![Err(SurfaceError::Outdated), Err(SurfaceError::Outdated)]), (Ok(None), 1));
+    }
+
+    #[test]
+    fn timeouts_skip_the_frame() {
+        assert_eq!(acquire(vec![Err(SurfaceError::Timeout)]), (Ok(None), 0));
+        assert_eq!(acquire(vec![Err(SurfaceError::Other)]), (Ok(None), 0));
+    }
+
+    #[test]
+    fn out_of_memory_is_fatal() {
+        assert_eq!(acquire(vec![Err(SurfaceError::OutOfMemory)]), (Err(SurfaceError::OutOfMemory), 0));
+        assert_eq!(acquire(vec![Err(SurfaceError::Lost), Err(SurfaceError::OutOfMemory)]), (Err(SurfaceError::OutOfMemory), 1));
+    }
+}
@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::error::{CuborumError, Result};
+
+/// Как часто проверять файлы шейдеров на изменения
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Шейдеры, из которых собираются пайплайны
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Shader {
+    Blit,
+    Bloom,
+    ComputeVoxel,
+    Cull,
+    Fxaa,
+    PathTrace,
+    PathTraceDisplay,
+    RayMarch,
+    Shadow,
+    Tonemap,
+    VoxelVertex,
+    VoxelFragment,
+}
+
+impl Shader {
+    pub const ALL: [Shader; 12] = [
+        Shader::Blit,
+        Shader::Bloom,
+        Shader::ComputeVoxel,
+        Shader::Cull,
+        Shader::Fxaa,
+        Shader::PathTrace,
+        Shader::PathTraceDisplay,
+        Shader::RayMarch,
+        Shader::Shadow,
+        Shader::Tonemap,
+        Shader::VoxelVertex,
+        Shader::VoxelFragment,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Shader::Blit => "blit.wgsl",
+            Shader::Bloom => "bloom.wgsl",
+            Shader::ComputeVoxel => "compute_voxel.wgsl",
+            Shader::Cull => "cull.wgsl",
+            Shader::Fxaa => "fxaa.wgsl",
+            Shader::PathTrace => "pathtrace.wgsl",
+            Shader::PathTraceDisplay => "pathtrace_display.wgsl",
+            Shader::RayMarch => "raymarch.wgsl",
+            Shader::Shadow => "shadow.wgsl",
+            Shader::Tonemap => "tonemap.wgsl",
+            Shader::VoxelVertex => "voxel_vertex.wgsl",
+            Shader::VoxelFragment => "voxel_fragment.wgsl",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Shader::Blit => "Blit Shader",
+            Shader::Bloom => "Bloom Shader",
+            Shader::ComputeVoxel => "Compute Shader",
+            Shader::Cull => "Cull Shader",
+            Shader::Fxaa => "FXAA Shader",
+            Shader::PathTrace => "Path Trace Shader",
+            Shader::PathTraceDisplay => "Path Trace Display Shader",
+            Shader::RayMarch => "Ray March Shader",
+            Shader::Shadow => "Shadow Shader",
+            Shader::Tonemap => "Tonemap Shader",
+            Shader::VoxelVertex => "Voxel Vertex Shader",
+            Shader::VoxelFragment => "Voxel Fragment Shader",
+        }
+    }
+
+    /// Исходник, вшитый в бинарник при сборке
+    fn embedded(self) -> &'static str {
+        match self {
+            Shader::Blit => include_str!("../../shaders/blit.wgsl"),
+            Shader::Bloom => include_str!("../../shaders/bloom.wgsl"),
+            Shader::ComputeVoxel => include_str!("../../shaders/compute_voxel.wgsl"),
+            Shader::Cull => include_str!("../../shaders/cull.wgsl"),
+            Shader::Fxaa => include_str!("../../shaders/fxaa.wgsl"),
+            Shader::PathTrace => include_str!("../../shaders/pathtrace.wgsl"),
+            Shader::PathTraceDisplay => include_str!("../../shaders/pathtrace_display.wgsl"),
+            Shader::RayMarch => include_str!("../../shaders/raymarch.wgsl"),
+            Shader::Shadow => include_str!("../../shaders/shadow.wgsl"),
+            Shader::Tonemap => include_str!("../../shaders/tonemap.wgsl"),
+            Shader::VoxelVertex => include_str!("../../shaders/voxel_vertex.wgsl"),
+            Shader::VoxelFragment => include_str!("../../shaders/voxel_fragment.wgsl"),
+        }
+    }
+}
+
+/// Общие куски WGSL для `#include "file.wgsl"`, лежат в `shaders/include/`
+pub const INCLUDES: [&str; 2] = ["post.wgsl", "voxel.wgsl"];
+
+fn embedded_include(name: &str) -> Option<&'static str> {
+    match name {
+        "post.wgsl" => Some(include_str!("../../shaders/include/post.wgsl")),
+        "voxel.wgsl" => Some(include_str!("../../shaders/include/voxel.wgsl")),
+        _ => None,
+    }
+}
+
+/// Откуда брать исходники шейдеров
+#[derive(Clone, Debug, Default)]
+pub enum ShaderSource {
+    /// Вшитые при сборке – бинарник не зависит от рабочего каталога
+    #[default]
+    Embedded,
+    /// Файлы из каталога (`--shader-dir`), для правки шейдеров на лету
+    Directory(PathBuf),
+}
+
+impl ShaderSource {
+    pub fn read(&self, shader: Shader) -> Result<Cow<'static, str>> {
+        match self {
+            ShaderSource::Embedded => Ok(Cow::Borrowed(shader.embedded())),
+            ShaderSource::Directory(dir) => {
+                let path = dir.join(shader.file_name());
+                std::fs::read_to_string(&path)
+                    .map(Cow::Owned)
+                    .map_err(|source| CuborumError::ShaderIo { path, source })
+            }
+        }
+    }
+
+    /// Файл из `include/`
+    pub fn read_include(&self, name: &str) -> Result<Cow<'static, str>> {
+        match self {
+            ShaderSource::Embedded => embedded_include(name).map(Cow::Borrowed).ok_or_else(|| CuborumError::ShaderIo {
+                path: PathBuf::from("include").join(name),
+                source: std::io::ErrorKind::NotFound.into(),
+            }),
+            ShaderSource::Directory(dir) => {
+                let path = dir.join("include").join(name);
+                std::fs::read_to_string(&path)
+                    .map(Cow::Owned)
+                    .map_err(|source| CuborumError::ShaderIo { path, source })
+            }
+        }
+    }
+}
+
+/// Следит за временем изменения файлов шейдеров в каталоге
+pub struct ShaderWatcher {
+    dir: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_check: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new(dir: PathBuf) -> Self {
+        let mut watcher = Self {
+            dir,
+            modified: HashMap::new(),
+            last_check: Instant::now(),
+        };
+        watcher.poll();
+        watcher
+    }
+
+    fn watched_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        let shaders = Shader::ALL.iter().map(|shader| self.dir.join(shader.file_name()));
+        shaders.chain(INCLUDES.iter().map(|name| self.dir.join("include").join(name)))
+    }
+
+    /// Обновляет времена изменения, возвращает изменившиеся файлы
+    fn poll(&mut self) -> Vec<PathBuf> {
+        let files: Vec<PathBuf> = self.watched_files().collect();
+        let mut changed = Vec::new();
+        for path in files {
+            let Ok(time) = std::fs::metadata(&path).and_then(|m| m.modified()) else { continue };
+            if self.modified.insert(path.clone(), time) != Some(time) {
+                changed.push(path);
+            }
+        }
+        changed
+    }
+
+    /// Шейдеры, файлы которых изменились с прошлой проверки (не чаще WATCH_INTERVAL).
+    /// Изменение общего include считается изменением всех шейдеров.
+    pub fn changed(&mut self) -> Vec<Shader> {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+
+        let changed = self.poll();
+        if changed.iter().any(|path| path.parent() != Some(self.dir.as_path())) {
+            return Shader::ALL.to_vec();
+        }
+        Shader::ALL
+            .into_iter()
+            .filter(|shader| changed.contains(&self.dir.join(shader.file_name())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directory_source_matches_embedded_shaders() {
+        let directory = ShaderSource::Directory(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("shaders"));
+        for shader in Shader::ALL {
+            assert_eq!(directory.read(shader).unwrap(), ShaderSource::Embedded.read(shader).unwrap());
+        }
+        for name in INCLUDES {
+            assert_eq!(directory.read_include(name).unwrap(), ShaderSource::Embedded.read_include(name).unwrap());
+        }
+    }
+
+    #[test]
+    fn watcher_reports_modified_files() {
+        let dir = std::env::temp_dir().join(format!("cuborum-shaders-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(Shader::Shadow.file_name());
+        std::fs::write(&path, "// v1").unwrap();
+
+        let mut watcher = ShaderWatcher::new(dir.clone());
+        watcher.last_check -= WATCH_INTERVAL;
+        assert!(watcher.changed().is_empty());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        watcher.last_check -= WATCH_INTERVAL;
+        assert_eq!(watcher.changed(), vec![Shader::Shadow]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
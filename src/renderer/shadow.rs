@@ -0,0 +1,276 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra::{Matrix4, Orthographic3, Point3, Vector3, Vector4};
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, TextureView};
+
+use crate::renderer::camera::{Camera, OPENGL_TO_WGPU_MATRIX};
+use crate::renderer::texture::DEPTH_FORMAT;
+
+/// Количество каскадов теневой карты
+pub const CASCADE_COUNT: usize = 3;
+/// Разрешение одного каскада
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+/// Дальше этого расстояния тени не считаются (дальняя плоскость камеры может быть гораздо дальше)
+pub const MAX_SHADOW_DISTANCE: f32 = 100.0;
+/// Вес логарифмического разбиения (0 – равномерное, 1 – логарифмическое)
+const SPLIT_LAMBDA: f32 = 0.6;
+
+/// Направленный источник света («солнце»)
+#[derive(Clone, Copy, Debug)]
+pub struct Sun {
+    pub direction: Vector3<f32>, // Направление лучей (от солнца к сцене)
+}
+
+impl Default for Sun {
+    fn default() -> Self {
+        Self {
+            direction: Vector3::new(-0.4, -1.0, -0.3).normalize(),
+        }
+    }
+}
+
+/// Uniform для основного прохода: матрицы каскадов, их границы и направление солнца
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ShadowUniform {
+    pub light_view_proj: [[[f32; 4]; 4]; CASCADE_COUNT],
+    pub splits: [f32; 4],        // Дальняя граница каждого каскада в глубине вида
+    pub sun_direction: [f32; 4], // xyz – направление, w не используется
+}
+
+/// Границы каскадов по глубине вида (практическая схема разбиения)
+pub fn cascade_splits(near: f32, far: f32) -> [f32; CASCADE_COUNT] {
+    let mut splits = [0.0; CASCADE_COUNT];
+    for (i, split) in splits.iter_mut().enumerate() {
+        let p = (i + 1) as f32 / CASCADE_COUNT as f32;
+        let log = near * (far / near).powf(p);
+        let uniform = near + (far - near) * p;
+        *split = SPLIT_LAMBDA * log + (1.0 - SPLIT_LAMBDA) * uniform;
+    }
+    splits
+}
+
+/// Матрица света для участка пирамиды видимости [near, far]
+fn cascade_matrix(camera: &Camera, sun: &Sun, near: f32, far: f32) -> Matrix4<f32> {
+    let inverse = (camera.projection_matrix_between(near, far) * camera.view_matrix())
+        .try_inverse()
+        .unwrap_or_else(Matrix4::identity);
+
+    // Углы участка пирамиды в мировых координатах
+    let mut corners = Vec::with_capacity(8);
+    for &x in &[-1.0, 1.0] {
+        for &y in &[-1.0, 1.0] {
+            for &z in &[0.0, 1.0] {
+                let p = inverse * Vector4::new(x, y, z, 1.0);
+                corners.push(Point3::from(p.xyz() / p.w));
+            }
+        }
+    }
+
+    let center = Point3::from(corners.iter().map(|c| c.coords).sum::<Vector3<f32>>() / 8.0);
+    // Радиус описанной сферы округляем, чтобы размер проекции не «дрожал»
+    let radius = corners
+        .iter()
+        .map(|c| (c - center).norm())
+        .fold(0.0_f32, f32::max);
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let up = if sun.direction.y.abs() > 0.99 { Vector3::z() } else { Vector3::y() };
+    let eye = center - sun.direction * radius * 2.0;
+    let view = Matrix4::look_at_rh(&eye, &center, &up);
+    let mut ortho = OPENGL_TO_WGPU_MATRIX
+        * Orthographic3::new(-radius, radius, -radius, radius, 0.0, radius * 4.0).to_homogeneous();
+
+    // Привязываем сдвиг к текселям карты, чтобы тени не мерцали при движении камеры
+    let origin = (ortho * view) * Vector4::new(0.0, 0.0, 0.0, 1.0);
+    let half = SHADOW_MAP_SIZE as f32 / 2.0;
+    let offset_x = ((origin.x * half).round() - origin.x * half) / half;
+    let offset_y = ((origin.y * half).round() - origin.y * half) / half;
+    ortho[(0, 3)] += offset_x;
+    ortho[(1, 3)] += offset_y;
+
+    ortho * view
+}
+
+/// Считает матрицы всех каскадов для текущей камеры
+pub fn compute_cascades(camera: &Camera, sun: &Sun) -> ShadowUniform {
+    let shadow_far = camera.far.min(MAX_SHADOW_DISTANCE);
+    let splits = cascade_splits(camera.near, shadow_far);
+    let mut uniform = ShadowUniform {
+        light_view_proj: [[[0.0; 4]; 4]; CASCADE_COUNT],
+        splits: [shadow_far; 4],
+        sun_direction: [sun.direction.x, sun.direction.y, sun.direction.z, 0.0],
+    };
+
+    let mut near = camera.near;
+    for (i, &far) in splits.iter().enumerate() {
+        let matrix = cascade_matrix(camera, sun, near, far);
+        uniform.light_view_proj[i] = matrix.into();
+        uniform.splits[i] = far;
+        near = far;
+    }
+    uniform
+}
+
+/// GPU-ресурсы теневой карты
+pub struct ShadowMap {
+    pub cascade_views: Vec<TextureView>,      // По одному слою на каскад (цель для shadow pass)
+    pub cascade_buffers: Vec<Buffer>,         // Матрица света каждого каскада
+    pub cascade_bind_groups: Vec<BindGroup>,  // Bind group'ы для shadow pass
+    pub uniform_buffer: Buffer,               // ShadowUniform для основного прохода
+    pub bind_group: BindGroup,                // Карта + сэмплер + uniform для основного прохода
+}
+
+impl ShadowMap {
+    pub fn new(
+        device: &Device,
+        shadow_bind_group_layout: &BindGroupLayout,
+        camera_bind_group_layout: &BindGroupLayout,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: CASCADE_COUNT as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Shadow Map Array View"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let cascade_views = (0..CASCADE_COUNT as u32)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Cascade View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let cascade_buffers: Vec<Buffer> = (0..CASCADE_COUNT)
+            .map(|_| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Shadow Cascade Buffer"),
+                    contents: bytemuck::cast_slice(Matrix4::<f32>::identity().as_slice()),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            })
+            .collect();
+
+        let cascade_bind_groups = cascade_buffers
+            .iter()
+            .map(|buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Cascade Bind Group"),
+                    layout: camera_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                })
+            })
+            .collect();
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            size: std::mem::size_of::<ShadowUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Сравнивающий сэмплер: линейная фильтрация даёт аппаратный 2×2 PCF
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout: shadow_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&array_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            cascade_views,
+            cascade_buffers,
+            cascade_bind_groups,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    /// Загружает матрицы каскадов в GPU
+    pub fn update(&self, queue: &wgpu::Queue, uniform: &ShadowUniform) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(uniform));
+        for (buffer, matrix) in self.cascade_buffers.iter().zip(uniform.light_view_proj.iter()) {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(matrix));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::camera::{CameraUniform, Projection};
+
+    /// Каскад, который выберет voxel_fragment.wgsl (`select_cascade`) для точки `point`
+    fn select_cascade(camera: &Camera, uniform: &ShadowUniform, point: &Point3<f32>) -> usize {
+        let [x, y, z, w] = CameraUniform::new(camera).view_depth;
+        let depth = Vector3::new(x, y, z).dot(&point.coords) + w;
+        uniform.splits[..CASCADE_COUNT].iter().position(|&split| depth < split).unwrap_or(CASCADE_COUNT - 1)
+    }
+
+    #[test]
+    fn each_depth_slice_gets_its_own_cascade_in_every_projection() {
+        for projection in [Projection::Perspective, Projection::Orthographic] {
+            let mut camera = Camera::new(1.5);
+            camera.set_projection(projection, 10.0);
+            let uniform = compute_cascades(&camera, &Sun::default());
+
+            let mut near = camera.near;
+            for (i, &far) in uniform.splits[..CASCADE_COUNT].iter().enumerate() {
+                let point = camera.position + camera.direction * (near + far) * 0.5;
+                assert_eq!(select_cascade(&camera, &uniform, &point), i, "{projection:?}");
+
+                // Точка попадает в карту своего каскада
+                let light = Matrix4::from(uniform.light_view_proj[i]) * point.to_homogeneous();
+                let ndc = light.xyz() / light.w;
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (0.0..=1.0).contains(&ndc.z), "{projection:?}");
+                near = far;
+            }
+        }
+    }
+}
@@ -1,13 +1,35 @@
 use std::sync::Arc;
-use wgpu::{Backends, Device, Queue, Instance, InstanceDescriptor, InstanceFlags, PowerPreference, RequestAdapterOptions};
+use wgpu::{Backends, Instance, InstanceDescriptor, InstanceFlags, PowerPreference, RequestAdapterOptions};
 use winit::window::Window;
-use crate::renderer::pipeline::{create_pipelines};
-use crate::renderer::camera::Camera;
-use crate::renderer::voxel::{Voxel, VoxelGrid};
+use crate::error::{CuborumError, Result};
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_compute_pipeline, create_pipelines, layout};
+use crate::renderer::camera::CameraUniform;
+use crate::simulation::Simulation;
+use crate::renderer::lod::{LodGrids, LodSettings};
+use crate::renderer::chunk::{build_chunks, ChunkBatch};
+use crate::renderer::culling::GpuCulling;
+use crate::renderer::mesh::MeshOptions;
+use crate::renderer::pathtrace::PathTracer;
+use crate::renderer::post::PostProcess;
+use crate::renderer::raymarch::{RayMarcher, RenderPath};
+use crate::renderer::shaders::{ShaderSource, ShaderWatcher};
+use crate::renderer::shadow::{ShadowMap, Sun};
+use crate::renderer::recovery::DeviceLost;
+use crate::renderer::msaa::Msaa;
+use crate::renderer::texture::{SceneTarget, DEPTH_FORMAT, HDR_FORMAT};
+use crate::renderer::timer::FrameTimer;
+use crate::renderer::graph::TransientPool;
+use crate::renderer::viewport::Viewport;
+use crate::renderer::voxel::Voxel;
 use wgpu::util::DeviceExt;
-use wgpu::{Surface, TextureFormat, BindGroup, Buffer};
 
-pub async fn initialize(window: Arc<Window>) -> crate::renderer::state::State {
+pub async fn initialize(
+    window: Arc<Window>,
+    mut sim: Simulation,
+    shaders: ShaderSource,
+    msaa_samples: u32,
+) -> Result<crate::renderer::state::State> {
     let instance = Instance::new(&InstanceDescriptor {
         backends: Backends::VULKAN | Backends::GL,
         flags: InstanceFlags::default(),
@@ -18,74 +40,63 @@ pub async fn initialize(window: Arc<Window>) -> crate::renderer::state::State {
         power_preference: PowerPreference::HighPerformance,
         compatible_surface: None,
         force_fallback_adapter: true,
-    }).await.expect("Failed to find a suitable GPU!");
+    }).await.ok_or(CuborumError::Adapter)?;
+
+    // GPU-отсечение с indirect-отрисовкой включаем, только если адаптер это умеет
+    let multi_draw_indirect = adapter.features().contains(wgpu::Features::MULTI_DRAW_INDIRECT);
+    let mut required_features = wgpu::Features::VERTEX_WRITABLE_STORAGE; // 🔥 Добавляем эту фичу
+    if multi_draw_indirect {
+        required_features |= wgpu::Features::MULTI_DRAW_INDIRECT;
+    }
+    // Без этой фичи MSAA доступен только с гарантированными 4 сэмплами
+    let adapter_formats = adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    if adapter_formats {
+        required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+    }
+    let format_flags = |format: wgpu::TextureFormat| {
+        if adapter_formats {
+            adapter.get_texture_format_features(format).flags
+        } else {
+            format.guaranteed_format_features(required_features).flags
+        }
+    };
+    // Цвет и глубина основного прохода должны поддерживать одно и то же число сэмплов
+    let msaa = Msaa::new(msaa_samples, |count| {
+        [HDR_FORMAT, DEPTH_FORMAT].into_iter().all(|format| format_flags(format).sample_count_supported(count))
+    });
+    if msaa.samples != msaa_samples {
+        tracing::info!("{}x MSAA is not supported, using {}x", msaa_samples, msaa.samples);
+    }
+    let scene_target = SceneTarget::hdr(msaa.samples);
 
     let (device, queue) = adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: Some("Cuborum Device"),
-            required_features: wgpu::Features::VERTEX_WRITABLE_STORAGE, // 🔥 Добавляем эту фичу
+            required_features,
             required_limits: adapter.limits().using_resolution(wgpu::Limits::downlevel_defaults()),
             memory_hints: wgpu::MemoryHints::default(),
         },
         None,
-    ).await.expect("Failed to create device!");
+    ).await?;
+    let device_lost = DeviceLost::watch(&device);
+    let mut pipelines = PipelineCache::new(&device, shaders);
 
-    let surface = instance.create_surface(window.clone()).unwrap();
-    let inner_size = window.inner_size().clone();
+    let surface = instance.create_surface(window.clone())?;
+    let inner_size = window.inner_size();
     let capabilities = surface.get_capabilities(&adapter);
-    let surface_format = capabilities.formats[0];
+    let surface_format = *capabilities.formats.first().ok_or(CuborumError::SurfaceUnsupported)?;
+    // Кадр выводится в sRGB-вид поверхности, если адаптер умеет такие виды; иначе кодирует blit-шейдер
+    let srgb_view = adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::VIEW_FORMATS);
+    let output_format = if srgb_view { surface_format.add_srgb_suffix() } else { surface_format };
 
-    // === Создаём Layout для камеры ===
-    let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Camera Bind Group Layout"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        }],
-    });
+    let camera_bind_group_layout = layout::bind_group_layout(&device, "Camera Bind Group Layout", &camera_layout_entries());
+    let shadow_bind_group_layout = layout::bind_group_layout(&device, "Shadow Bind Group Layout", &shadow_layout_entries());
+    let voxel_compute_bind_group_layout =
+        layout::bind_group_layout(&device, "Voxel Compute Bind Group Layout", &voxel_compute_layout_entries());
+    let voxel_render_bind_group_layout =
+        layout::bind_group_layout(&device, "Voxel Render Bind Group Layout", &voxel_render_layout_entries());
 
-    let binding_size = std::num::NonZeroU64::new(8).unwrap();
-    println!("DEBUG===================================");
-    println!("binding_size: {:?}", binding_size);
-
-    // === Создаём Layout для вокселей ===
-    let voxel_compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Voxel Compute Bind Group Layout"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::COMPUTE,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Storage { read_only: false },
-                has_dynamic_offset: false,
-                min_binding_size: Some(binding_size),
-            },
-            count: None,
-        }],
-    });
-
-    let voxel_render_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Voxel Render Bind Group Layout"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Storage { read_only: true },
-                has_dynamic_offset: false,
-                min_binding_size: Some(binding_size),
-            },
-            count: None,
-        }],
-    });
-
-
-    let mut voxel_grid = VoxelGrid::new(16);
-    voxel_grid.fill_with_test_pattern();
+    let voxel_grid = &sim.voxel_grid;
 
     let voxel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Voxel Buffer"),
@@ -93,6 +104,34 @@ pub async fn initialize(window: Arc<Window>) -> crate::renderer::state::State {
         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX, // ✅ Добавили `VERTEX`
     });
 
+    // === Освещение и параметры сетки ===
+    let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Light Buffer"),
+        contents: bytemuck::cast_slice(&sim.light_volume.packed()),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+    let grid_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Grid Uniform Buffer"),
+        contents: bytemuck::bytes_of(&voxel_grid.uniform()),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    // === Строим меши чанков ===
+    let lod_settings = LodSettings::default();
+    let lod_grids = LodGrids::new(voxel_grid, lod_settings.color);
+    let mesh_options = MeshOptions {
+        seal_borders: lod_settings.enabled,
+        ..Default::default()
+    };
+    let chunks = build_chunks(voxel_grid, &lod_grids, mesh_options);
+    let chunk_batch = ChunkBatch::new(&device, &chunks);
+    let gpu_culling = multi_draw_indirect.then(|| GpuCulling::new(&device, &mut pipelines, &chunk_batch)).transpose()?;
+    if gpu_culling.is_none() {
+        tracing::info!("MULTI_DRAW_INDIRECT is not supported, falling back to CPU culling");
+    }
+    let viewport = Viewport::new(inner_size, window.scale_factor());
+    sim.camera.aspect_ratio = viewport.aspect_ratio();
+
     // === Создаём BindGroup для вокселей ===
     let voxel_compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Voxel Compute Bind Group"),
@@ -106,26 +145,45 @@ pub async fn initialize(window: Arc<Window>) -> crate::renderer::state::State {
     let voxel_render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Voxel Render Bind Group"),
         layout: &voxel_render_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: voxel_buffer.as_entire_binding(),
-        }],
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: voxel_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: light_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: grid_buffer.as_entire_binding(),
+            },
+        ],
     });
 
     // === Создаём пайплайны ===
-    let (compute_pipeline, voxel_pipeline) = create_pipelines(
-        &device, 
-        surface_format, 
-        &voxel_compute_bind_group_layout,
+    let compute_pipeline =
+        create_compute_pipeline(&mut pipelines, &voxel_compute_bind_group_layout, sim.voxel_grid.size as u32)?;
+    let (voxel_pipeline, shadow_pipeline) = create_pipelines(
+        &mut pipelines,
+        scene_target,
         &voxel_render_bind_group_layout,
-        &camera_bind_group_layout
-    );
+        &camera_bind_group_layout,
+        &shadow_bind_group_layout,
+    )?;
+
+    let raymarcher = RayMarcher::new(&device, &mut pipelines, scene_target, &voxel_render_bind_group_layout)?;
+    let pathtracer = PathTracer::new(&device, &mut pipelines, scene_target, inner_size, &voxel_buffer, &light_buffer, &grid_buffer)?;
+    let post = PostProcess::new(&device, &mut pipelines, output_format)?;
 
-    let camera = Camera::new(1.0);
-    let camera_matrix = camera.view_proj_matrix();
+    // === Теневая карта ===
+    let sun = Sun::default();
+    let shadow_map = ShadowMap::new(&device, &shadow_bind_group_layout, &camera_bind_group_layout);
+
+    let camera_uniform = CameraUniform::new(&sim.camera);
     let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Camera Buffer"),
-        contents: bytemuck::cast_slice(camera_matrix.as_ref()),
+        contents: bytemuck::bytes_of(&camera_uniform),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
@@ -139,21 +197,82 @@ pub async fn initialize(window: Arc<Window>) -> crate::renderer::state::State {
         }],
     });
 
-    crate::renderer::state::State {
+    Ok(crate::renderer::state::State {
         window,
         device,
         queue,
-        size: inner_size,
+        viewport,
         surface,
         surface_format,
         compute_pipeline,
         voxel_pipeline,
-        voxel_grid,
+        shadow_pipeline,
+        sim,
         voxel_buffer,
+        light_buffer,
+        mesh_options,
+        lod_settings,
+        lod_grids,
+        chunks,
+        chunk_batch,
+        gpu_culling,
+        voxel_compute_bind_group_layout,
+        voxel_render_bind_group_layout,
         voxel_compute_bind_group,
         voxel_render_bind_group,
-        camera,
         camera_buffer,
+        camera_bind_group_layout,
         camera_bind_group,
-    }
+        shadow_bind_group_layout,
+        sun,
+        shadow_map,
+        render_path: RenderPath::Raster,
+        raymarcher,
+        pathtracer,
+        post,
+        msaa,
+        transients: TransientPool::default(),
+        dump_render_graph: false,
+        timer: FrameTimer::new(),
+        device_lost,
+        shader_watcher: match &pipelines.shaders {
+            ShaderSource::Directory(dir) => Some(ShaderWatcher::new(dir.clone())),
+            ShaderSource::Embedded => None,
+        },
+        pipelines,
+        presets_file: None,
+        recorder: None,
+        replay: None,
+    })
+}
+
+/// Раскладка группы камеры: uniform с матрицей вид-проекция
+pub fn camera_layout_entries() -> [wgpu::BindGroupLayoutEntry; 1] {
+    [layout::uniform(0, wgpu::ShaderStages::VERTEX)]
+}
+
+/// Раскладка группы теней: uniform каскадов + карта глубины + сэмплер сравнения
+pub fn shadow_layout_entries() -> [wgpu::BindGroupLayoutEntry; 3] {
+    let fragment = wgpu::ShaderStages::FRAGMENT;
+    [
+        layout::uniform(0, fragment),
+        layout::depth_texture_array(1, fragment),
+        layout::comparison_sampler(2, fragment),
+    ]
+}
+
+/// Раскладка вокселей для compute-прохода (запись)
+pub fn voxel_compute_layout_entries() -> [wgpu::BindGroupLayoutEntry; 1] {
+    [layout::storage_array::<Voxel>(0, wgpu::ShaderStages::COMPUTE, false)]
+}
+
+/// Раскладка вокселей для рендера: воксели, уровни освещения и параметры сетки
+pub fn voxel_render_layout_entries() -> [wgpu::BindGroupLayoutEntry; 3] {
+    let fragment = wgpu::ShaderStages::FRAGMENT;
+    [
+        // Фрагменты читают воксели при трассировке
+        layout::storage_array::<Voxel>(0, wgpu::ShaderStages::VERTEX | fragment, true),
+        layout::storage(1, fragment, true), // Уровни освещения (по u32 на ячейку)
+        layout::uniform(2, fragment),       // Параметры сетки
+    ]
 }
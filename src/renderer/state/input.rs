@@ -1,21 +1,153 @@
+use crate::bindings::Action;
+use crate::config::CONFIG_FILE;
 use crate::renderer::state::State;
-use winit::event::WindowEvent;
+use crate::renderer::lod::LodColor;
+use crate::renderer::pathtrace::ProgressiveTarget;
+use crate::renderer::post::EXPOSURE_STEP;
+use crate::renderer::raymarch::RenderPath;
+use crate::simulation::{Effect, InputEvent};
+use std::path::{Path, PathBuf};
+use winit::event::{DeviceEvent, WindowEvent};
+use winit::window::CursorGrabMode;
+
+/// Сколько сэмплов на пиксель копит прогрессивный рендер
+const PROGRESSIVE_SAMPLES: u32 = 256;
 
 pub fn process_input(state: &mut State, event: &WindowEvent) {
-    match event {
-        WindowEvent::KeyboardInput { event, .. } => {
-            match event.state {
-                winit::event::ElementState::Pressed => {
-                    state.camera.pressed_keys.insert(event.logical_key.clone());
-                }
-                winit::event::ElementState::Released => {
-                    state.camera.pressed_keys.remove(&event.logical_key);
+    if let Some(event) = InputEvent::from_window_event(event) {
+        process_live_event(state, event);
+    }
+}
+
+pub fn process_device_input(state: &mut State, event: &DeviceEvent) {
+    if let Some(event) = InputEvent::from_device_event(event) {
+        process_live_event(state, event);
+    }
+}
+
+/// Событие от пользователя: пишется в запись, во время воспроизведения игнорируется
+fn process_live_event(state: &mut State, event: InputEvent) {
+    if state.replay.is_some() {
+        return;
+    }
+    if let Some(recorder) = &mut state.recorder {
+        recorder.record(state.sim.tick, &event);
+    }
+    apply_event(state, &event);
+}
+
+/// Применяет событие к симуляции и выполняет то, что она попросила
+pub fn apply_event(state: &mut State, event: &InputEvent) {
+    match state.sim.apply(event) {
+        Some(Effect::Action(action)) => run_action(state, action),
+        Some(Effect::VoxelChanged(cell)) => state.voxel_changed(cell),
+        Some(Effect::GrabMouse(grab)) => set_mouse_grab(state, grab),
+        // Закладки из записи живут только в памяти, файл пользователя воспроизведение не трогает
+        Some(Effect::CameraPresetsChanged) if state.replay.is_some() => (),
+        Some(Effect::CameraPresetsChanged) => {
+            if let Some(path) = &state.presets_file {
+                if let Err(err) = state.sim.presets.save(path) {
+                    tracing::error!("Failed to save {}: {}", path.display(), err);
                 }
             }
         }
-        WindowEvent::CursorMoved { position, .. } => {
-            state.camera.process_mouse(position.x as f32, position.y as f32);
+        None => (),
+    }
+}
+
+/// Захватывает мышь и прячет курсор (или отпускает)
+fn set_mouse_grab(state: &mut State, grab: bool) {
+    let window = &state.window;
+    if grab {
+        // Locked есть не везде (например, X11), тогда хотя бы держим курсор в окне
+        let result = window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined));
+        if let Err(err) = result {
+            tracing::warn!("Failed to grab cursor: {}", err);
+            state.sim.mouse_grabbed = false;
+            return;
+        }
+    } else if let Err(err) = window.set_cursor_grab(CursorGrabMode::None) {
+        tracing::warn!("Failed to release cursor: {}", err);
+    }
+    window.set_cursor_visible(!grab);
+}
+
+/// Действия рендера, срабатывающие при нажатии
+fn run_action(state: &mut State, action: Action) {
+    match action {
+        // Растр / трассировка лучами / трассировка путей (меш перестраивать не нужно)
+        Action::CycleRenderPath => {
+            state.render_path = match state.render_path {
+                RenderPath::Raster => RenderPath::RayMarch,
+                RenderPath::RayMarch => RenderPath::PathTrace,
+                RenderPath::PathTrace => RenderPath::Raster,
+            };
+            return;
+        }
+        // Прогрессивный рендер: копим сэмплы с нуля и сохраняем PNG
+        Action::ProgressiveRender => {
+            state.render_path = RenderPath::PathTrace;
+            state.pathtracer.reset();
+            state.pathtracer.progressive = Some(ProgressiveTarget {
+                samples: PROGRESSIVE_SAMPLES,
+                path: PathBuf::from("pathtrace.png"),
+            });
+            return;
+        }
+        Action::SaveConfig => {
+            let path = Path::new(CONFIG_FILE);
+            match state.config().save(path) {
+                Ok(()) => tracing::info!("Config saved to {}", path.display()),
+                Err(err) => tracing::error!("Failed to save {}: {}", path.display(), err),
+            }
+            return;
+        }
+        // Граф следующего кадра сохраняется в DOT для отладки
+        Action::DumpRenderGraph => {
+            state.dump_render_graph = true;
+            return;
+        }
+        // Этапы постобработки: меняется только граф следующего кадра
+        Action::CycleTonemap | Action::ToggleBloom | Action::ToggleFxaa | Action::IncreaseExposure | Action::DecreaseExposure => {
+            let settings = &mut state.post.settings;
+            match action {
+                Action::CycleTonemap => settings.tonemap = settings.tonemap.next(),
+                Action::ToggleBloom => settings.bloom = !settings.bloom,
+                Action::ToggleFxaa => settings.fxaa = !settings.fxaa,
+                Action::IncreaseExposure => settings.scale_exposure(EXPOSURE_STEP),
+                _ => settings.scale_exposure(1.0 / EXPOSURE_STEP),
+            }
+            tracing::info!("Post-processing: {:?}", settings);
+            return;
+        }
+        // 1 → 2 → 4 → 8 → 1 сэмплов, пропуская неподдерживаемые
+        Action::CycleMsaa => {
+            let samples = state.msaa.next();
+            match state.set_msaa_samples(samples) {
+                Ok(()) => tracing::info!("MSAA: {}x", state.msaa.samples),
+                Err(err) => tracing::error!("Failed to switch to {}x MSAA: {}", samples, err),
+            }
+            return;
+        }
+        Action::ToggleAmbientOcclusion => {
+            state.mesh_options.ambient_occlusion = !state.mesh_options.ambient_occlusion;
+        }
+        // Уровни детализации (с ними запечатываем границы чанков от щелей)
+        Action::ToggleLod => {
+            state.lod_settings.enabled = !state.lod_settings.enabled;
+            state.mesh_options.seal_borders = state.lod_settings.enabled;
+        }
+        // Цвет грубых уровней: среднее / самый частый
+        Action::ToggleLodColor => {
+            state.lod_settings.color = match state.lod_settings.color {
+                LodColor::Average => LodColor::Majority,
+                LodColor::Majority => LodColor::Average,
+            };
         }
-        _ => (),
+        // Камерой и удерживаемыми действиями занимается симуляция
+        _ => return,
     }
+    state.rebuild_mesh();
 }
@@ -1,63 +1,251 @@
 pub mod init;
 pub mod render;
 pub mod input;
+pub mod reload;
 pub mod update;
 
+use std::path::PathBuf;
 use std::sync::Arc;
-use wgpu::{BindGroup, Buffer, CommandEncoder, ComputePipeline, Device, Queue, RenderPipeline, Surface, TextureFormat};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue, RenderPipeline, Surface, TextureFormat};
 use winit::window::Window;
-use crate::renderer::camera::Camera;
-use crate::renderer::voxel::VoxelGrid;
+use crate::config::{Config, GraphicsConfig, MouseConfig};
+use crate::error::Result;
+use crate::replay::{InputRecorder, Replay};
+use crate::simulation::Simulation;
+use crate::renderer::lod::{LodGrids, LodSettings, LOD_COUNT};
+use crate::renderer::chunk::{affected_chunks, build_chunks, chunk_index, ChunkBatch, ChunkMesh};
+use crate::renderer::culling::GpuCulling;
+use crate::renderer::graph::TransientPool;
+use crate::renderer::mesh::MeshOptions;
+use crate::renderer::msaa::Msaa;
+use crate::renderer::pathtrace::PathTracer;
+use crate::renderer::post::PostProcess;
+use crate::renderer::pipeline::cache::PipelineCache;
+use crate::renderer::pipeline::{create_pathtrace_display_pipeline, create_raymarch_pipeline, create_voxel_pipeline};
+use crate::renderer::pipeline::compute::VOXEL_WORKGROUP_SIZE;
+use crate::renderer::raymarch::{RayMarcher, RenderPath};
+use crate::renderer::recovery::DeviceLost;
+use crate::renderer::shaders::{ShaderSource, ShaderWatcher};
+use crate::renderer::shadow::{ShadowMap, Sun};
+use crate::renderer::texture::SceneTarget;
+use crate::renderer::timer::FrameTimer;
+use crate::renderer::viewport::Viewport;
 use winit::dpi::PhysicalSize;
 
 pub struct State {
     pub window: Arc<Window>,
     pub device: Device,
     pub queue: Queue,
-    pub size: PhysicalSize<u32>,
+    pub viewport: Viewport, // Размер окна и цели рендера, зависящие от него
     pub surface: Surface<'static>,
     pub surface_format: TextureFormat,
     pub compute_pipeline: ComputePipeline,
     pub voxel_pipeline: RenderPipeline,
-    pub voxel_grid: VoxelGrid,
+    pub shadow_pipeline: RenderPipeline,
+    pub sim: Simulation, // Камера, ввод и сетка вокселей (без GPU)
     pub voxel_buffer: Buffer,
+    pub light_buffer: Buffer,
+    pub mesh_options: MeshOptions,
+    pub lod_settings: LodSettings,
+    pub lod_grids: LodGrids,
+    pub chunks: Vec<ChunkMesh>,
+    pub chunk_batch: ChunkBatch,
+    pub gpu_culling: Option<GpuCulling>, // None – отсечение на CPU
+    pub voxel_compute_bind_group_layout: BindGroupLayout,
+    pub voxel_render_bind_group_layout: BindGroupLayout,
     pub voxel_compute_bind_group: BindGroup,
     pub voxel_render_bind_group: BindGroup,
-    pub camera: Camera,
     pub camera_buffer: Buffer,
+    pub camera_bind_group_layout: BindGroupLayout,
     pub camera_bind_group: BindGroup,
+    pub shadow_bind_group_layout: BindGroupLayout,
+    pub sun: Sun,
+    pub shadow_map: ShadowMap,
+    pub render_path: RenderPath,
+    pub raymarcher: RayMarcher,
+    pub pathtracer: PathTracer,
+    pub post: PostProcess, // HDR-кадр → экран
+    pub msaa: Msaa,
+    pub transients: TransientPool, // Временные текстуры графа кадра
+    pub dump_render_graph: bool,   // Сохранить граф следующего кадра в DOT
+    pub timer: FrameTimer,
+    pub device_lost: DeviceLost,
+    pub pipelines: PipelineCache, // Собранные пайплайны и источник шейдеров
+    pub shader_watcher: Option<ShaderWatcher>, // Только для шейдеров из каталога
+    pub presets_file: Option<PathBuf>, // Куда сохранять закладки; None – только в памяти
+    pub recorder: Option<InputRecorder>,
+    pub replay: Option<Replay>,
 }
 
 impl State {
-    pub async fn new(window: Arc<Window>) -> Self {
-        init::initialize(window).await
+    /// `msaa_samples` – желаемое число сэмплов MSAA, урезается до поддерживаемого адаптером
+    pub async fn new(window: Arc<Window>, sim: Simulation, shaders: ShaderSource, msaa_samples: u32) -> Result<Self> {
+        let state = init::initialize(window, sim, shaders, msaa_samples).await?;
+        // Resized с тем же размером поверхность уже не настроит
+        if !state.viewport.is_empty() {
+            state.configure_surface();
+        }
+        Ok(state)
     }
 
-    pub fn render(&mut self) {
-        render::render(self);
+    pub fn render(&mut self) -> Result<()> {
+        render::render(self)
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.is_lost()
+    }
+
+    /// Пересоздаёт устройство, поверхность, пайплайны и буферы после потери устройства.
+    /// Сетка, камера и настройки живут на CPU и переносятся как есть.
+    pub async fn recreate(self) -> Result<Self> {
+        tracing::warn!("Recreating GPU resources after device loss");
+        // Старые поверхность и устройство освобождаются до создания новых
+        let (window, sim, shaders, mesh_options, lod_settings, render_path, post_settings, msaa_samples, timer, presets_file, recorder, replay) = {
+            let state = self;
+            (
+                state.window,
+                state.sim,
+                state.pipelines.shaders,
+                state.mesh_options,
+                state.lod_settings,
+                state.render_path,
+                state.post.settings,
+                state.msaa.samples,
+                state.timer,
+                state.presets_file,
+                state.recorder,
+                state.replay,
+            )
+        };
+
+        let mut state = State::new(window, sim, shaders, msaa_samples).await?;
+        state.mesh_options = mesh_options;
+        state.lod_settings = lod_settings;
+        state.render_path = render_path;
+        state.post.settings = post_settings;
+        state.timer = timer;
+        state.presets_file = presets_file;
+        state.recorder = recorder;
+        state.replay = replay;
+        state.rebuild_mesh();
+        Ok(state)
     }
 
     pub fn input(&mut self, event: &winit::event::WindowEvent) {
         input::process_input(self, event);
     }
 
+    pub fn device_input(&mut self, event: &winit::event::DeviceEvent) {
+        input::process_device_input(self, event);
+    }
+
     pub fn update(&mut self) {
         update::update(self);
     }
 
+    /// Новый размер окна: поверхность, цели рендера и проекция камеры меняются вместе.
+    /// Свёрнутое окно (нулевой размер) ничего не пересоздаёт, рендер его пропускает.
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
-        self.size = new_size;
+        if !self.viewport.resize(new_size) {
+            return;
+        }
+        let logical = self.viewport.logical_size();
+        tracing::debug!("Viewport resized to {}x{} ({:.0}x{:.0} logical)", new_size.width, new_size.height, logical.width, logical.height);
+        self.sim.camera.aspect_ratio = self.viewport.aspect_ratio();
         self.configure_surface();
+        // Покадровые цели графа подстроятся под viewport сами, буфер накопления живёт дольше кадра
+        self.pathtracer.resize(&self.device, new_size);
+    }
+
+    /// Цель основного прохода с текущим числом сэмплов MSAA
+    pub fn scene_target(&self) -> SceneTarget {
+        SceneTarget::hdr(self.msaa.samples)
+    }
+
+    /// Меняет число сэмплов MSAA (урезая до поддерживаемого) и пересобирает пайплайны основного прохода.
+    /// Цели рендера граф создаст заново сам: у них меняется описание.
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<()> {
+        let target = SceneTarget::hdr(self.msaa.clamp(samples));
+        let pipelines = &mut self.pipelines;
+        let voxel_pipeline = create_voxel_pipeline(
+            pipelines,
+            target,
+            &self.voxel_render_bind_group_layout,
+            &self.camera_bind_group_layout,
+            &self.shadow_bind_group_layout,
+        )?;
+        let raymarch_pipeline = create_raymarch_pipeline(
+            pipelines,
+            target,
+            &self.raymarcher.bind_group_layout,
+            &self.voxel_render_bind_group_layout,
+        )?;
+        let display_pipeline = create_pathtrace_display_pipeline(pipelines, target, &self.pathtracer.display_bind_group_layout)?;
+
+        self.voxel_pipeline = voxel_pipeline;
+        self.raymarcher.pipeline = raymarch_pipeline;
+        self.pathtracer.display_pipeline = display_pipeline;
+        self.msaa.samples = target.samples;
+        Ok(())
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.viewport.scale_factor = scale_factor;
+    }
+
+    /// Загружает изменённый симуляцией воксель и освещение, перестраивает затронутые чанки
+    pub fn voxel_changed(&mut self, cell: [usize; 3]) {
+        self.upload_voxels();
+        let grid = &self.sim.voxel_grid;
+        self.lod_grids.update(grid, cell, self.lod_settings.color);
+
+        // Ячейка влияет на грани и AO соседей, поэтому на каждом уровне перестраиваем и чанки через границу.
+        // Меши заменяются в общих буферах на месте; если какой-то вырос больше запаса – собираем буферы заново.
+        let mut fits = true;
+        for level in 0..LOD_COUNT {
+            for coords in affected_chunks(grid, cell, level) {
+                let index = chunk_index(grid, coords);
+                let chunk = &mut self.chunks[index];
+                chunk.rebuild_lod(grid, &self.lod_grids, self.mesh_options, level);
+                fits &= self.chunk_batch.update(&self.queue, index, level, &chunk.lods[level]);
+            }
+        }
+        if !fits {
+            self.upload_chunks();
+        }
+    }
+
+    /// Перестраивает меши всех чанков и загружает в GPU воксели и освещение
+    pub fn rebuild_mesh(&mut self) {
+        self.upload_voxels();
+        self.lod_grids = LodGrids::new(&self.sim.voxel_grid, self.lod_settings.color);
+        self.chunks = build_chunks(&self.sim.voxel_grid, &self.lod_grids, self.mesh_options);
+        self.upload_chunks();
+    }
+
+    /// Собирает чанки в общие буферы и обновляет привязки GPU-отсечения
+    fn upload_chunks(&mut self) {
+        self.chunk_batch = ChunkBatch::new(&self.device, &self.chunks);
+        if let Some(culling) = &mut self.gpu_culling {
+            culling.rebind(&self.device, &self.chunk_batch);
+        }
+    }
+
+    fn upload_voxels(&mut self) {
+        self.pathtracer.reset(); // Сцена изменилась – накопленные сэмплы устарели
+        self.queue.write_buffer(&self.voxel_buffer, 0, bytemuck::cast_slice(&self.sim.voxel_grid.data));
+        self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&self.sim.light_volume.packed()));
     }
 
     fn configure_surface(&self) {
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: self.surface_format,
-            view_formats: vec![self.surface_format.add_srgb_suffix()],
+            view_formats: vec![self.post.output_format],
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            width: self.size.width,
-            height: self.size.height,
+            width: self.viewport.size.width,
+            height: self.viewport.size.height,
             desired_maximum_frame_latency: 2,
             present_mode: wgpu::PresentMode::AutoVsync,
         };
@@ -72,11 +260,55 @@ impl State {
 
         compute_pass.set_pipeline(&self.compute_pipeline);
         compute_pass.set_bind_group(0, &self.voxel_compute_bind_group, &[]);
-        compute_pass.dispatch_workgroups(2, 2, 2); // 🟢 Запускаем compute shader
+        let groups = (self.sim.voxel_grid.size as u32).div_ceil(VOXEL_WORKGROUP_SIZE);
+        compute_pass.dispatch_workgroups(groups, groups, groups); // 🟢 Запускаем compute shader
 
         drop(compute_pass);
     }
 
+    /// Рендерит меш сетки в каждый каскад теневой карты
+    pub fn run_shadow_passes(&self, encoder: &mut CommandEncoder) {
+        for (view, bind_group) in self.shadow_map.cascade_views.iter().zip(&self.shadow_map.cascade_bind_groups) {
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            shadow_pass.set_pipeline(&self.shadow_pipeline);
+            shadow_pass.set_bind_group(0, bind_group, &[]);
+            // Тени отбрасывают и чанки вне поля зрения, поэтому здесь без отсечения
+            if self.chunk_batch.is_empty() {
+                continue;
+            }
+            self.chunk_batch.bind(&mut shadow_pass);
+            for draw in self.chunk_batch.selected(&self.lod_settings, &self.sim.camera.position) {
+                ChunkBatch::draw(&mut shadow_pass, draw);
+            }
+        }
+    }
+
+    /// Текущие настройки в виде, пригодном для сохранения
+    pub fn config(&self) -> Config {
+        Config {
+            mouse: MouseConfig {
+                sensitivity: self.sim.camera.sensitivity,
+                invert_y: self.sim.camera.invert_y,
+            },
+            bindings: self.sim.controls.bindings.clone(),
+            graphics: GraphicsConfig { msaa: self.msaa.samples },
+        }
+    }
+
     pub fn get_window(&self) -> &Window {
         &self.window
     }
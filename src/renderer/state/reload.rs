@@ -0,0 +1,112 @@
+use crate::error::Result;
+use crate::renderer::pipeline::{
+    create_bloom_pipelines, create_blit_pipeline, create_compute_pipeline, create_cull_pipeline, create_fxaa_pipeline,
+    create_pathtrace_display_pipeline, create_pathtrace_pipeline, create_raymarch_pipeline, create_shadow_pipeline,
+    create_tonemap_pipeline, create_voxel_pipeline,
+};
+use crate::renderer::shaders::Shader;
+use crate::renderer::state::State;
+use crate::renderer::texture::LDR_FORMAT;
+
+/// Пересобирает пайплайны, чьи шейдеры изменились в каталоге `--shader-dir`.
+/// Кэш забывает только зависящее от изменившихся шейдеров, остальное берётся из него как есть.
+/// Если новый WGSL не собирается, остаётся старый пайплайн, а ошибка уходит в лог.
+pub fn reload_changed_shaders(state: &mut State) {
+    let Some(watcher) = &mut state.shader_watcher else { return };
+    let changed = watcher.changed();
+    if changed.is_empty() {
+        return;
+    }
+    state.pipelines.invalidate(&changed);
+    let uses = |shaders: &[Shader]| shaders.iter().any(|shader| changed.contains(shader));
+    let target = state.scene_target();
+    let grid_size = state.sim.voxel_grid.size as u32;
+    let pipelines = &mut state.pipelines;
+
+    if uses(&[Shader::ComputeVoxel]) {
+        let created = create_compute_pipeline(pipelines, &state.voxel_compute_bind_group_layout, grid_size);
+        if let Some(pipeline) = reloaded("compute", created) {
+            state.compute_pipeline = pipeline;
+        }
+    }
+    if uses(&[Shader::VoxelVertex, Shader::VoxelFragment]) {
+        let created = create_voxel_pipeline(
+            pipelines,
+            target,
+            &state.voxel_render_bind_group_layout,
+            &state.camera_bind_group_layout,
+            &state.shadow_bind_group_layout,
+        );
+        if let Some(pipeline) = reloaded("voxel", created) {
+            state.voxel_pipeline = pipeline;
+        }
+    }
+    if uses(&[Shader::Shadow]) {
+        if let Some(pipeline) = reloaded("shadow", create_shadow_pipeline(pipelines, &state.camera_bind_group_layout)) {
+            state.shadow_pipeline = pipeline;
+        }
+    }
+    if uses(&[Shader::RayMarch]) {
+        let raymarcher = &mut state.raymarcher;
+        let created = create_raymarch_pipeline(pipelines, target, &raymarcher.bind_group_layout, &state.voxel_render_bind_group_layout);
+        if let Some(pipeline) = reloaded("ray march", created) {
+            raymarcher.pipeline = pipeline;
+        }
+    }
+    let pathtracer = &mut state.pathtracer;
+    if uses(&[Shader::PathTrace]) {
+        if let Some(pipeline) = reloaded("path trace", create_pathtrace_pipeline(pipelines, &pathtracer.compute_bind_group_layout)) {
+            pathtracer.compute_pipeline = pipeline;
+            pathtracer.reset(); // Накопленное старым шейдером не смешиваем с новым
+        }
+    }
+    if uses(&[Shader::PathTraceDisplay]) {
+        let created = create_pathtrace_display_pipeline(pipelines, target, &pathtracer.display_bind_group_layout);
+        if let Some(pipeline) = reloaded("path trace display", created) {
+            pathtracer.display_pipeline = pipeline;
+        }
+    }
+    if let (true, Some(culling)) = (uses(&[Shader::Cull]), &mut state.gpu_culling) {
+        if let Some(pipeline) = reloaded("cull", create_cull_pipeline(pipelines, &culling.bind_group_layout)) {
+            culling.pipeline = pipeline;
+        }
+    }
+    let post = &mut state.post;
+    if uses(&[Shader::Bloom]) {
+        if let Some(bloom) = reloaded("bloom", create_bloom_pipelines(pipelines, &post.bind_group_layout)) {
+            post.bloom_pipelines = bloom;
+        }
+    }
+    if uses(&[Shader::Tonemap]) {
+        if let Some(pipeline) = reloaded("tonemap", create_tonemap_pipeline(pipelines, &post.tonemap_bind_group_layout)) {
+            post.tonemap_pipeline = pipeline;
+        }
+    }
+    if uses(&[Shader::Fxaa]) {
+        if let Some(pipeline) = reloaded("FXAA", create_fxaa_pipeline(pipelines, &post.bind_group_layout)) {
+            post.fxaa_pipeline = pipeline;
+        }
+    }
+    if uses(&[Shader::Blit]) {
+        if let Some(pipeline) = reloaded("blit", create_blit_pipeline(pipelines, "Blit Pipeline", &post.bind_group_layout, post.output_format)) {
+            post.blit_pipeline = pipeline;
+        }
+        if let Some(pipeline) = reloaded("export blit", create_blit_pipeline(pipelines, "Export Blit Pipeline", &post.bind_group_layout, LDR_FORMAT)) {
+            post.export_pipeline = pipeline;
+        }
+    }
+}
+
+/// Новый пайплайн или None, если старый остаётся (ошибка уходит в лог)
+fn reloaded<T>(label: &str, created: Result<T>) -> Option<T> {
+    match created {
+        Ok(pipeline) => {
+            tracing::info!("Reloaded {} pipeline", label);
+            Some(pipeline)
+        }
+        Err(err) => {
+            tracing::error!("Keeping the old {} pipeline: {}", label, err);
+            None
+        }
+    }
+}
@@ -1,40 +1,179 @@
+use std::path::Path;
+
+use wgpu::TextureView;
+
+use crate::error::Result;
+use crate::renderer::camera::CameraUniform;
+use crate::renderer::chunk::ChunkBatch;
+use crate::renderer::graph::{RenderGraph, TextureDesc, RENDER_GRAPH_FILE};
+use crate::renderer::raymarch::{RayMarchUniform, RenderPath};
+use crate::renderer::recovery::acquire_frame;
+use crate::renderer::shadow::compute_cascades;
 use crate::renderer::state::State;
-use wgpu::{TextureView, CommandEncoder};
+use crate::renderer::texture::{MainTargets, HDR_FORMAT};
+
+/// Рисует кадр. Ошибка означает, что продолжать нельзя (кончилась память);
+/// временные сбои поверхности обрабатываются здесь же, кадр просто пропускается.
+pub fn render(state: &mut State) -> Result<()> {
+    if state.viewport.is_empty() {
+        return Ok(()); // Окно свёрнуто
+    }
+    let Some(surface_texture) = acquire_frame(|| state.surface.get_current_texture(), || state.configure_surface())? else {
+        return Ok(());
+    };
+    let camera = CameraUniform::new(&state.sim.camera);
+    state.queue.write_buffer(&state.camera_buffer, 0, bytemuck::bytes_of(&camera));
 
-pub fn render(state: &mut State) {
-    let camera_matrix = state.camera.projection_matrix() * state.camera.view_matrix();
-    state.queue.write_buffer(&state.camera_buffer, 0, bytemuck::cast_slice(camera_matrix.as_slice()));
+    // Uniform-ы и счётчики обновляются до сборки графа: проходам состояние нужно только для чтения
+    match state.render_path {
+        RenderPath::Raster => {
+            let shadow_uniform = compute_cascades(&state.sim.camera, &state.sun);
+            state.shadow_map.update(&state.queue, &shadow_uniform);
+        }
+        RenderPath::RayMarch => {
+            let uniform = RayMarchUniform::new(&state.sim.camera, &state.sun, state.viewport.size);
+            state.raymarcher.update(&state.queue, &uniform);
+        }
+        RenderPath::PathTrace => state.pathtracer.update(&state.queue, &state.sim.camera, &state.sun),
+    }
+    state.post.update(&state.queue);
 
-    let surface_texture = state.surface.get_current_texture().expect("Failed to acquire next swapchain texture");
-    let texture_view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    // Вид в sRGB-формате (если он есть): кодирование в sRGB при записи делает сама поверхность
+    let texture_view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor {
+        format: Some(state.post.output_format),
+        ..Default::default()
+    });
+    let mut transients = std::mem::take(&mut state.transients);
+    let dump_render_graph = std::mem::take(&mut state.dump_render_graph);
 
     let mut encoder = state.device.create_command_encoder(&Default::default());
+    let graph = frame_graph(state, &texture_view);
+    if dump_render_graph {
+        let path = Path::new(RENDER_GRAPH_FILE);
+        match std::fs::write(path, graph.to_dot()) {
+            Ok(()) => tracing::info!("Render graph saved to {}", path.display()),
+            Err(err) => tracing::error!("Failed to save {}: {}", path.display(), err),
+        }
+    }
+    let executed = graph.execute(&state.device, &mut encoder, &mut transients);
+    state.transients = transients;
+    executed?;
 
-    state.run_compute_pass(&mut encoder);
-
-    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: None,
-        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &texture_view,
-            resolve_target: None,
-            ops: wgpu::Operations {
-                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                store: wgpu::StoreOp::Store,
-            },
-        })],
-        depth_stencil_attachment: None,
-        timestamp_writes: None,
-        occlusion_query_set: None,
-    });
+    state.queue.submit([encoder.finish()]);
+    if let Some(culling) = &state.gpu_culling {
+        culling.submitted();
+    }
+    state.window.pre_present_notify();
+    let suboptimal = surface_texture.suboptimal;
+    surface_texture.present();
+    if suboptimal {
+        state.configure_surface();
+    }
+
+    if state.render_path == RenderPath::PathTrace {
+        state.pathtracer.save_if_done(&state.device, &state.queue, &state.post, state.msaa.samples);
+    }
+    Ok(())
+}
+
+/// Проходы кадра для текущего пути рендера и настроек
+fn frame_graph<'a>(state: &'a State, surface: &'a TextureView) -> RenderGraph<'a> {
+    let mut graph = RenderGraph::new();
+    let size = state.viewport.size;
+    let voxels = graph.import("voxels");
+    let surface = graph.import_texture("surface", surface);
+    // Сцена рисуется в HDR, на экран кадр выводит цепочка постобработки
+    let color = graph.transient("hdr", TextureDesc::target(size, HDR_FORMAT).sampled());
+    let main = MainTargets::new(&mut graph, size, state.msaa.samples, color);
 
+    graph.pass("Voxel Compute").writes(&[voxels]).record(|encoder, _| state.run_compute_pass(encoder));
+
+    match state.render_path {
+        RenderPath::Raster => {
+            let shadow_map = graph.import("shadow map");
+            graph.pass("Shadow").writes(&[shadow_map]).record(|encoder, _| state.run_shadow_passes(encoder));
+
+            let mut reads = vec![voxels, shadow_map];
+            // Без GPU-отсечения видимость считается на CPU при записи прохода вокселей
+            if let Some(culling) = &state.gpu_culling {
+                let indirect = graph.import("indirect draws");
+                graph.pass("Cull").writes(&[indirect]).record(move |encoder, _| {
+                    culling.run(&state.queue, encoder, &state.sim.camera, &state.lod_settings, &state.chunk_batch);
+                });
+                reads.push(indirect);
+            }
+
+            graph.pass("Voxel").reads(&reads).writes(&main.ids()).record(move |encoder, resources| {
+                let mut render_pass = main.begin(encoder, resources);
+                draw_chunks(state, &mut render_pass);
+            });
+        }
+        RenderPath::RayMarch => {
+            graph.pass("Ray March").reads(&[voxels]).writes(&main.ids()).record(move |encoder, resources| {
+                let mut render_pass = main.begin(encoder, resources);
+                render_pass.set_pipeline(&state.raymarcher.pipeline);
+                render_pass.set_bind_group(0, &state.raymarcher.bind_group, &[]);
+                render_pass.set_bind_group(1, &state.voxel_render_bind_group, &[]); // Буфер вокселей
+                render_pass.draw(0..3, 0..1);
+            });
+        }
+        RenderPath::PathTrace => {
+            let accumulation = graph.import("accumulation");
+            graph
+                .pass("Path Trace")
+                .reads(&[voxels])
+                .writes(&[accumulation])
+                .record(|encoder, _| state.pathtracer.record(encoder));
+            graph
+                .pass("Path Trace Display")
+                .reads(&[accumulation])
+                .writes(&main.ids())
+                .record(move |encoder, resources| {
+                    let mut render_pass = main.begin(encoder, resources);
+                    state.pathtracer.draw(&mut render_pass);
+                });
+        }
+    }
+
+    state.post.add_passes(&mut graph, &state.device, size, color, surface);
+    graph
+}
+
+/// Растровый путь: меши чанков с отсечением на GPU или CPU
+fn draw_chunks<'a>(state: &'a State, render_pass: &mut wgpu::RenderPass<'a>) {
     render_pass.set_bind_group(0, &state.camera_bind_group, &[]);
     render_pass.set_bind_group(1, &state.voxel_render_bind_group, &[]); // Воксели
+    render_pass.set_bind_group(2, &state.shadow_map.bind_group, &[]); // Тени
     render_pass.set_pipeline(&state.voxel_pipeline);
-    render_pass.draw(0..(state.voxel_grid.size * state.voxel_grid.size * state.voxel_grid.size) as u32, 0..1);
-
-    drop(render_pass);
 
-    state.queue.submit([encoder.finish()]);
-    state.window.pre_present_notify();
-    surface_texture.present();
+    if state.chunk_batch.is_empty() {
+        return;
+    }
+    state.chunk_batch.bind(render_pass);
+    match &state.gpu_culling {
+        // Видимость уже записана compute-проходом в indirect-буфер
+        Some(_) => {
+            render_pass.multi_draw_indexed_indirect(
+                &state.chunk_batch.indirect_buffer,
+                0,
+                state.chunk_batch.draws.len() as u32,
+            );
+        }
+        // Рисуем только чанки, попадающие в пирамиду видимости
+        None => {
+            let frustum = state.sim.camera.frustum();
+            let mut drawn = 0;
+            let mut culled = 0;
+            for draw in state.chunk_batch.selected(&state.lod_settings, &state.sim.camera.position) {
+                // Сначала дешёвая проверка сферой, затем точнее по AABB
+                if !frustum.intersects_aabb_sphere(&draw.bounds) || !frustum.intersects_aabb(&draw.bounds) {
+                    culled += 1;
+                    continue;
+                }
+                ChunkBatch::draw(render_pass, draw);
+                drawn += 1;
+            }
+            tracing::debug!(drawn, culled, "Frustum culling");
+        }
+    }
 }
@@ -1,13 +1,22 @@
+use crate::renderer::state::input::apply_event;
+use crate::renderer::state::reload::reload_changed_shaders;
 use crate::renderer::state::State;
 
 pub fn update(state: &mut State) {
-    // Обновляем камеру (перемещение и повороты)
-    state.camera.process_keyboard();
-    
-    // Если в будущем будут обновления вокселей (например, добавление или удаление),
-    // мы будем загружать их в GPU-буфер здесь:
-    //
-    // state.queue.write_buffer(&state.voxel_buffer, 0, bytemuck::cast_slice(&state.voxel_grid.data));
+    reload_changed_shaders(state);
 
-    // Логика обновления других элементов игры может идти здесь
+    // Симуляция идёт фиксированными шагами, сколько их накопилось за кадр
+    for _ in 0..state.timer.fixed_steps() {
+        if let Some(replay) = &mut state.replay {
+            let finished = replay.is_finished();
+            for event in replay.take(state.sim.tick) {
+                apply_event(state, &event);
+            }
+            if finished {
+                tracing::info!("Replay finished at tick {}", state.sim.tick);
+                state.replay = None;
+            }
+        }
+        state.sim.step();
+    }
 }
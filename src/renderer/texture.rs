@@ -0,0 +1,88 @@
+use wgpu::{CommandEncoder, TextureFormat, TextureView};
+use winit::dpi::PhysicalSize;
+
+use crate::renderer::graph::{PassResources, RenderGraph, ResourceId, TextureDesc};
+
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/// Кадр до тонмаппинга: значения выше 1 сохраняются для bloom и экспозиции
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// Кадр после тонмаппинга; sRGB-формат хранит линейный цвет без полос в тенях
+pub const LDR_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Цель, в которую рисуется сцена (растр, трассировка лучами, вывод трассировки путей)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SceneTarget {
+    pub format: TextureFormat,
+    pub samples: u32, // MSAA; с глубиной того же числа сэмплов
+}
+
+impl SceneTarget {
+    /// HDR-кадр с `samples` сэмплами на пиксель
+    pub const fn hdr(samples: u32) -> Self {
+        Self { format: HDR_FORMAT, samples }
+    }
+}
+
+/// Цели основного прохода. С MSAA сцена рисуется в многосэмпловые цвет и глубину,
+/// а цвет в конце прохода усредняется в `resolve` (HDR-кадр для постобработки).
+#[derive(Clone, Copy)]
+pub struct MainTargets {
+    pub color: ResourceId,
+    pub resolve: Option<ResourceId>,
+    pub depth: ResourceId,
+}
+
+impl MainTargets {
+    pub fn new(graph: &mut RenderGraph, size: PhysicalSize<u32>, samples: u32, hdr: ResourceId) -> Self {
+        let depth = graph.transient("depth", TextureDesc::target(size, DEPTH_FORMAT).samples(samples));
+        if samples == 1 {
+            return Self { color: hdr, resolve: None, depth };
+        }
+        let color = graph.transient("hdr msaa", TextureDesc::target(size, HDR_FORMAT).samples(samples));
+        Self { color, resolve: Some(hdr), depth }
+    }
+
+    pub fn ids(&self) -> Vec<ResourceId> {
+        [self.color, self.depth].into_iter().chain(self.resolve).collect()
+    }
+
+    /// Начинает проход, очищая цвет и глубину
+    pub fn begin<'e>(&self, encoder: &'e mut CommandEncoder, resources: &PassResources) -> wgpu::RenderPass<'e> {
+        begin_main_pass(
+            encoder,
+            resources.view(self.color),
+            self.resolve.map(|resolve| resources.view(resolve)),
+            resources.view(self.depth),
+        )
+    }
+}
+
+/// Основной проход: очищает цвет и глубину. Многосэмпловый цвет после усреднения в `resolve` не нужен.
+fn begin_main_pass<'e>(
+    encoder: &'e mut CommandEncoder,
+    color: &TextureView,
+    resolve: Option<&TextureView>,
+    depth: &TextureView,
+) -> wgpu::RenderPass<'e> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Main Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: color,
+            resolve_target: resolve,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: if resolve.is_some() { wgpu::StoreOp::Discard } else { wgpu::StoreOp::Store },
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: depth,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    })
+}
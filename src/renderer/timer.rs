@@ -0,0 +1,50 @@
+use std::time::Instant;
+
+use crate::simulation::FIXED_DT;
+
+/// Дольше этого кадр не считается: после паузы (перетаскивание окна, отладчик)
+/// камера не должна улетать одним скачком
+const MAX_FRAME_TIME: f32 = 0.1;
+
+/// Время между кадрами
+pub struct FrameTimer {
+    last: Instant,
+    accumulator: f32, // Время, ещё не отданное шагам симуляции
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            accumulator: 0.0,
+        }
+    }
+
+    /// Секунды с прошлого вызова
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last).as_secs_f32();
+        self.last = now;
+        dt.min(MAX_FRAME_TIME)
+    }
+
+    /// Сколько шагов FIXED_DT уложилось в прошедшее время (остаток переносится)
+    pub fn fixed_steps(&mut self) -> u32 {
+        let dt = self.tick();
+        self.steps_for(dt)
+    }
+
+    /// То же для кадра длиной `dt` секунд
+    pub fn steps_for(&mut self, dt: f32) -> u32 {
+        self.accumulator += dt;
+        let steps = (self.accumulator / FIXED_DT) as u32;
+        self.accumulator -= steps as f32 * FIXED_DT;
+        steps
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
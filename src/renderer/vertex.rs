@@ -6,10 +6,13 @@ use bytemuck::{Pod, Zeroable};
 pub struct Vertex {
     pub position: [f32; 3], 
     pub color: [f32; 4],   
+    pub ao: f32, // Затенение вершины: 0 – полностью закрыта, 1 – открыта
+    pub normal: [f32; 3],
+    pub emission: f32, // 1 у граней светящихся вокселей
 }
 
 impl Vertex {
-    pub const ATTRIBS: [wgpu::VertexAttribute; 2] = [
+    pub const ATTRIBS: [wgpu::VertexAttribute; 5] = [
         wgpu::VertexAttribute {
             offset: 0,
             shader_location: 0,
@@ -20,33 +23,44 @@ impl Vertex {
             shader_location: 1,
             format: wgpu::VertexFormat::Float32x4, // Цвет
         },
+        wgpu::VertexAttribute {
+            offset: 28,
+            shader_location: 2,
+            format: wgpu::VertexFormat::Float32, // AO
+        },
+        wgpu::VertexAttribute {
+            offset: 32,
+            shader_location: 3,
+            format: wgpu::VertexFormat::Float32x3, // Нормаль
+        },
+        wgpu::VertexAttribute {
+            offset: 44,
+            shader_location: 4,
+            format: wgpu::VertexFormat::Float32, // Свечение
+        },
     ];
-}
 
-pub fn create_vertex_buffer(device: &wgpu::Device) -> wgpu::Buffer {
-    let vertices = [
-        Vertex { position: [-0.5, -0.5, 0.0], color: [1.0, 0.0, 0.0, 1.0] }, // Красный
-        Vertex { position: [ 0.5, -0.5, 0.0], color: [0.0, 1.0, 0.0, 1.0] }, // Зелёный
-        Vertex { position: [ 0.5,  0.5, 0.0], color: [0.0, 0.0, 1.0, 1.0] }, // Синий
-        Vertex { position: [-0.5,  0.5, 0.0], color: [1.0, 1.0, 0.0, 1.0] }, // Жёлтый
-    ];
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
 
+pub fn create_vertex_buffer(device: &wgpu::Device, vertices: &[Vertex]) -> wgpu::Buffer {
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(&vertices),
-        usage: wgpu::BufferUsages::VERTEX,
+        contents: bytemuck::cast_slice(vertices),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
     })
 }
 
-pub fn create_index_buffer(device: &wgpu::Device) -> wgpu::Buffer {
-    let indices: [u16; 6] = [
-        0, 1, 2,
-        2, 3, 0,
-    ];
-
+pub fn create_index_buffer(device: &wgpu::Device, indices: &[u32]) -> wgpu::Buffer {
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(&indices),
-        usage: wgpu::BufferUsages::INDEX,
+        contents: bytemuck::cast_slice(indices),
+        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
     })
 }
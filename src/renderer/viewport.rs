@@ -0,0 +1,74 @@
+use winit::dpi::{LogicalSize, PhysicalSize};
+
+/// Область вывода: размер окна в пикселях и масштаб DPI.
+/// Цели рендера этого размера, живущие один кадр (глубина, HDR, MSAA, постобработка), выделяет граф кадра
+/// (`RenderGraph`) по текущему `size`. Исключение – буфер накопления трассировщика путей: он переживает
+/// кадры, поэтому в пул графа не годится и пересоздаётся в `State::resize` вместе с поверхностью.
+pub struct Viewport {
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+}
+
+impl Viewport {
+    pub fn new(size: PhysicalSize<u32>, scale_factor: f64) -> Self {
+        Self { size, scale_factor }
+    }
+
+    /// Окно свёрнуто – рисовать некуда
+    pub fn is_empty(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
+    /// Отношение ширины к высоте; у пустой области 1, чтобы проекция оставалась конечной
+    pub fn aspect_ratio(&self) -> f32 {
+        aspect_ratio(self.size)
+    }
+
+    pub fn logical_size(&self) -> LogicalSize<f64> {
+        self.size.to_logical(self.scale_factor)
+    }
+
+    /// Запоминает новый размер.
+    /// Возвращает false, если пересоздавать нечего: размер тот же или окно свёрнуто.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) -> bool {
+        if size == self.size {
+            return false;
+        }
+        self.size = size;
+        !self.is_empty()
+    }
+}
+
+fn aspect_ratio(size: PhysicalSize<u32>) -> f32 {
+    if size.width == 0 || size.height == 0 {
+        return 1.0;
+    }
+    size.width as f32 / size.height as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aspect_ratio_stays_finite_when_minimized() {
+        assert_eq!(aspect_ratio(PhysicalSize::new(1600, 900)), 1600.0 / 900.0);
+        assert_eq!(aspect_ratio(PhysicalSize::new(1600, 0)), 1.0);
+        assert_eq!(aspect_ratio(PhysicalSize::new(0, 0)), 1.0);
+    }
+
+    #[test]
+    fn resize_reports_when_targets_must_be_recreated() {
+        let mut viewport = Viewport::new(PhysicalSize::new(800, 600), 1.0);
+        assert!(!viewport.resize(PhysicalSize::new(800, 600)));
+        assert!(viewport.resize(PhysicalSize::new(1024, 768)));
+        assert_eq!(viewport.size, PhysicalSize::new(1024, 768));
+
+        // Свёрнутое окно запоминается, но пересоздавать нечего; после разворачивания – снова нужно
+        assert!(!viewport.resize(PhysicalSize::new(1024, 0)));
+        assert!(viewport.is_empty());
+        assert_eq!(viewport.aspect_ratio(), 1.0);
+        assert!(viewport.resize(PhysicalSize::new(1024, 768)));
+        assert!(!viewport.is_empty());
+    }
+}
@@ -1,32 +1,81 @@
-#[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Voxel {
-    pub voxel_type: u32,   // 4 байта 
-    pub color: u32, 
+use nalgebra::Point3;
+
+use crate::renderer::frustum::Aabb;
+use crate::renderer::light::MAX_LIGHT;
+use crate::renderer::pipeline::common::wgsl_struct;
+
+/// Типы вокселей
+pub const VOXEL_EMPTY: u32 = 0;
+pub const VOXEL_SOLID: u32 = 1;
+pub const VOXEL_EMISSIVE: u32 = 2; // Светится собственным цветом
+
+/// Сдвиги байтов R, G, B, A в упакованном цвете (R в старшем).
+/// По ним же генерируется `unpack_color` для шейдеров.
+pub const COLOR_SHIFTS: [u32; 4] = [24, 16, 8, 0];
+
+/// Во сколько раз светящийся воксель ярче белого при растре и трассировке лучами:
+/// в HDR-кадре он выходит за 1 и попадает в bloom
+pub const EMISSIVE_STRENGTH: f32 = 4.0;
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct Voxel as "Voxel" {
+        pub voxel_type: u32, // 4 байта
+        pub color: u32,
+    }
 }
 
 impl Voxel {
     pub fn empty() -> Self {
         Self {
-            voxel_type: 0,
+            voxel_type: VOXEL_EMPTY,
             color: 0, 
         }
     }
 
     pub fn new(voxel_type: u32, r: u8, g: u8, b: u8, a: u8) -> Self {
-        let packed_color = (r as u32) << 24 | (g as u32) << 16 | (b as u32) << 8 | (a as u32);
+        let packed_color = [r, g, b, a].into_iter().zip(COLOR_SHIFTS).fold(0, |packed, (c, shift)| packed | (c as u32) << shift);
         Self {
             voxel_type,
             color: packed_color,
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.voxel_type == VOXEL_EMPTY
+    }
+
+    /// Цветной свет, который испускает воксель (по уровню на канал RGB)
+    pub fn emission(&self) -> [u8; 3] {
+        if self.voxel_type != VOXEL_EMISSIVE {
+            return [0; 3];
+        }
+        let scale = |shift: u32| (((self.color >> shift) & 0xFF) * MAX_LIGHT as u32 + 127) / 255;
+        let [r, g, b, _] = COLOR_SHIFTS;
+        [r, g, b].map(|shift| scale(shift) as u8)
+    }
+
     pub fn unpack_color(&self) -> [f32; 4] {
-        let r = ((self.color >> 24) & 0xFF) as f32 / 255.0;
-        let g = ((self.color >> 16) & 0xFF) as f32 / 255.0;
-        let b = ((self.color >> 8) & 0xFF) as f32 / 255.0;
-        let a = (self.color & 0xFF) as f32 / 255.0;
-        [r, g, b, a]
+        COLOR_SHIFTS.map(|shift| ((self.color >> shift) & 0xFF) as f32 / 255.0)
+    }
+
+    /// `unpack_color` на WGSL для `#include <types>`
+    pub fn unpack_color_wgsl() -> String {
+        let [r, g, b, a] = COLOR_SHIFTS.map(|shift| format!("f32((packed >> {}u) & 0xFFu)", shift));
+        format!("fn unpack_color(packed: u32) -> vec4<f32> {{\n    return vec4<f32>({}, {}, {}, {}) / 255.0;\n}}\n", r, g, b, a)
+    }
+}
+
+wgsl_struct! {
+    /// Параметры сетки для шейдеров
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct GridUniform as "Grid" {
+        pub origin: [f32; 3],
+        pub voxel_size: f32,
+        pub size: u32,
+        pub _padding: [u32; 3],
     }
 }
 
@@ -52,6 +101,40 @@ impl VoxelGrid {
         &self.data[self.get_index(x, y, z)]
     }
 
+    /// Проверяет, занята ли ячейка; координаты вне сетки считаются пустыми
+    pub fn is_solid(&self, pos: [i32; 3]) -> bool {
+        let size = self.size as i32;
+        if pos.iter().any(|&c| c < 0 || c >= size) {
+            return false;
+        }
+        !self.get(pos[0] as usize, pos[1] as usize, pos[2] as usize).is_empty()
+    }
+
+    /// Размер одного вокселя в мировых координатах (вся сетка занимает куб [-1, 1])
+    pub fn voxel_size(&self) -> f32 {
+        2.0 / self.size as f32
+    }
+
+    /// Мировая позиция угла (0, 0, 0) сетки
+    pub fn origin(&self) -> [f32; 3] {
+        [-1.0, -1.0, -1.0]
+    }
+
+    /// Мировые границы всей сетки
+    pub fn bounds(&self) -> Aabb {
+        let min = Point3::from(self.origin());
+        Aabb { min, max: min + nalgebra::Vector3::repeat(self.size as f32 * self.voxel_size()) }
+    }
+
+    pub fn uniform(&self) -> GridUniform {
+        GridUniform {
+            origin: self.origin(),
+            voxel_size: self.voxel_size(),
+            size: self.size as u32,
+            _padding: [0; 3],
+        }
+    }
+
     pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: Voxel) {
         let index = self.get_index(x, y, z);
         self.data[index] = voxel;
@@ -65,13 +148,19 @@ impl VoxelGrid {
                         x,
                         y,
                         z,
-                        Voxel {
-                            voxel_type: 1,
-                            color: 128 
-                        },
+                        Voxel::new(VOXEL_SOLID, 255, 0, 0, 255),
                     );
                 }
             }
         }
+
+        // Несколько столбиков поверх плиты, чтобы было видно затенение в углах
+        for z in (2..self.size).step_by(5) {
+            for x in (2..self.size).step_by(5) {
+                for y in self.size / 2..self.size * 3 / 4 {
+                    self.set(x, y, z, Voxel::new(VOXEL_SOLID, 255, 0, 0, 255));
+                }
+            }
+        }
     }
 }
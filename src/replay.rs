@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Bindings;
+use crate::config::{Config, MouseConfig};
+use crate::renderer::camera_path::CameraPresets;
+use crate::simulation::{InputEvent, FIXED_DT};
+
+/// Первая строка записи: всё, от чего зависит результат воспроизведения, кроме самих событий
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub fixed_dt: f32,
+    pub mouse: MouseConfig,
+    pub bindings: Bindings,
+    /// Закладки и пути на момент записи: воспроизведение не зависит от текущего файла
+    #[serde(default)]
+    pub presets: CameraPresets,
+}
+
+impl RecordingHeader {
+    pub fn new(config: &Config, presets: &CameraPresets) -> Self {
+        Self {
+            fixed_dt: FIXED_DT,
+            mouse: config.mouse,
+            bindings: config.bindings.clone(),
+            presets: presets.clone(),
+        }
+    }
+
+    /// Настройки, с которыми была сделана запись
+    pub fn config(&self) -> Config {
+        Config {
+            mouse: self.mouse,
+            bindings: self.bindings.clone(),
+            ..Default::default() // Графика на воспроизведение не влияет
+        }
+    }
+}
+
+/// Событие с номером шага симуляции, перед которым оно пришло
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub tick: u64,
+    pub event: InputEvent,
+}
+
+/// Пишет события в файл, по одному JSON-объекту на строку
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path, header: &RecordingHeader) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self { writer })
+    }
+
+    pub fn record(&mut self, tick: u64, event: &InputEvent) {
+        let result = serde_json::to_writer(&mut self.writer, &RecordedEvent { tick, event: *event })
+            .map_err(std::io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(err) = result {
+            tracing::error!("Failed to record input: {}", err);
+        }
+    }
+}
+
+/// Загруженная запись, события выдаются по шагам
+pub struct Replay {
+    pub header: RecordingHeader,
+    events: VecDeque<RecordedEvent>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header_line = lines
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "empty recording"))??;
+        let header: RecordingHeader = serde_json::from_str(&header_line)?;
+        if header.fixed_dt != FIXED_DT {
+            tracing::warn!("Recording uses a {} s step, replaying with {} s", header.fixed_dt, FIXED_DT);
+        }
+
+        let mut events = VecDeque::new();
+        for line in lines {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push_back(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self { header, events })
+    }
+
+    /// События, пришедшие перед шагом `tick`
+    pub fn take(&mut self, tick: u64) -> Vec<InputEvent> {
+        let mut taken = Vec::new();
+        while let Some(recorded) = self.events.front() {
+            if recorded.tick > tick {
+                break;
+            }
+            taken.push(recorded.event);
+            self.events.pop_front();
+        }
+        taken
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Шаг, после которого записанных событий больше нет
+    pub fn last_tick(&self) -> u64 {
+        self.events.back().map_or(0, |e| e.tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::camera_path::{Bookmark, Pose};
+    use crate::renderer::timer::FrameTimer;
+    use crate::renderer::voxel::Voxel;
+    use crate::simulation::{Button, Simulation};
+    use winit::keyboard::KeyCode;
+
+    fn simulation(config: &Config) -> Simulation {
+        Simulation::with_test_pattern(config)
+    }
+
+    /// События живого запуска по номеру кадра, в котором они пришли
+    fn script() -> Vec<(usize, InputEvent)> {
+        let key = |frame, key, pressed| (frame, InputEvent::Key { key, pressed, repeat: false });
+        vec![
+            (0, InputEvent::Button { button: Button::Left, pressed: true }), // Захват мыши
+            (1, InputEvent::MouseMotion { dx: 0.0, dy: 100.0 }), // Взгляд на плиту
+            (1, InputEvent::Button { button: Button::Right, pressed: true }), // Лампа перед плитой
+            (1, InputEvent::Button { button: Button::Left, pressed: true }),  // Удаление
+            (1, InputEvent::MouseMotion { dx: 40.0, dy: -15.0 }),
+            key(2, KeyCode::KeyW, true),
+            key(2, KeyCode::ShiftLeft, true),
+            key(25, KeyCode::ShiftLeft, false),
+            (30, InputEvent::MouseMotion { dx: -12.0, dy: 7.0 }),
+            key(45, KeyCode::KeyW, false),
+            (48, InputEvent::Button { button: Button::Left, pressed: true }),
+            key(50, KeyCode::Tab, true),
+            (51, InputEvent::Wheel { lines: 2.0 }),
+            key(55, KeyCode::KeyB, true), // Следующая закладка – из заголовка записи
+        ]
+    }
+
+    /// Длительности кадров живого запуска: частота кадров скачет
+    const FRAME_TIMES: [f32; 6] = [1.0 / 60.0, 1.0 / 144.0, 1.0 / 30.0, 0.011, 1.0 / 90.0, 0.05];
+
+    #[test]
+    fn replay_reproduces_a_run_recorded_at_varying_frame_rate() {
+        let path = std::env::temp_dir().join(format!("cuborum-replay-{}.jsonl", std::process::id()));
+        let pose = Pose { position: [1.0, 2.0, 3.0], yaw: 10.0, pitch: -5.0, fov: 50.0 };
+        let presets = CameraPresets { bookmarks: vec![Bookmark { name: "start".to_string(), pose }], ..Default::default() };
+
+        // Живой запуск: события применяются и пишутся по мере прихода, шаги – сколько накопилось за кадр
+        let mut recorder = InputRecorder::create(&path, &RecordingHeader::new(&Config::default(), &presets)).unwrap();
+        let mut live = simulation(&Config::default());
+        live.presets = presets.clone();
+        let mut timer = FrameTimer::new();
+        let script = script();
+        for frame in 0..80 {
+            for (_, event) in script.iter().filter(|(at, _)| *at == frame) {
+                recorder.record(live.tick, event);
+                live.apply(event);
+            }
+            for _ in 0..timer.steps_for(FRAME_TIMES[frame % FRAME_TIMES.len()]) {
+                live.step();
+            }
+        }
+        drop(recorder);
+
+        let mut replay = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let mut sim = simulation(&replay.header.config());
+        sim.presets = replay.header.presets.clone();
+        while sim.tick < live.tick {
+            for event in replay.take(sim.tick) {
+                sim.apply(&event);
+            }
+            sim.step();
+        }
+
+        assert!(replay.is_finished());
+        assert_eq!(Pose::capture(&sim.camera).position, pose.position);
+        assert_eq!(sim.camera.view_matrix(), live.camera.view_matrix());
+        let voxels = |sim: &Simulation| bytemuck::cast_slice::<Voxel, u8>(&sim.voxel_grid.data).to_vec();
+        assert_eq!(voxels(&sim), voxels(&live));
+        assert_ne!(voxels(&sim), voxels(&simulation(&Config::default())), "the run should edit voxels");
+    }
+}
@@ -0,0 +1,343 @@
+use nalgebra::Point3;
+use serde::{Deserialize, Serialize};
+use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::bindings::{Action, Controls};
+use crate::config::Config;
+use crate::renderer::camera::{AxisView, Camera, CameraMode};
+use crate::renderer::camera_path::{Bookmark, CameraPath, CameraPresets, Keyframe, PathPlayback, Pose};
+use crate::renderer::light::LightVolume;
+use crate::renderer::orbit::OrbitDrag;
+use crate::renderer::picking::raycast;
+use crate::renderer::voxel::{Voxel, VoxelGrid, VOXEL_EMISSIVE};
+
+/// Шаг симуляции в секундах. Камера и мир обновляются только фиксированными шагами,
+/// поэтому запись ввода воспроизводится одинаково при любой частоте кадров.
+pub const FIXED_DT: f32 = 1.0 / 120.0;
+
+/// Пикселей прокрутки тачпада на одно деление колеса
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// Путь, в который добавляются ключевые кадры с клавиатуры
+const RECORDED_PATH: &str = "recorded";
+/// Секунд между ключевыми кадрами, добавленными с клавиатуры
+const KEYFRAME_INTERVAL: f32 = 2.0;
+
+/// Цвета ламп, которые ставятся правой кнопкой
+const LAMP_COLORS: [(u8, u8, u8); 3] = [(255, 180, 80), (80, 160, 255), (120, 255, 120)];
+
+/// Кнопки мыши, которые что-то делают
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Button {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Ввод, не зависящий от окна: его можно записать в файл и воспроизвести
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputEvent {
+    Key { key: KeyCode, pressed: bool, repeat: bool },
+    Button { button: Button, pressed: bool },
+    Wheel { lines: f32 },
+    MouseMotion { dx: f32, dy: f32 },
+    FocusLost,
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                // Физические клавиши: назначения не зависят от раскладки
+                let PhysicalKey::Code(key) = event.physical_key else { return None };
+                Some(Self::Key {
+                    key,
+                    pressed: event.state == ElementState::Pressed,
+                    repeat: event.repeat,
+                })
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => Button::Left,
+                    MouseButton::Right => Button::Right,
+                    MouseButton::Middle => Button::Middle,
+                    _ => return None,
+                };
+                Some(Self::Button { button, pressed: *state == ElementState::Pressed })
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                Some(Self::Wheel { lines })
+            }
+            WindowEvent::Focused(false) => Some(Self::FocusLost),
+            _ => None,
+        }
+    }
+
+    /// Сырое движение мыши: не упирается в край экрана и не прыгает при входе курсора в окно
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta: (dx, dy) } => Some(Self::MouseMotion { dx: *dx as f32, dy: *dy as f32 }),
+            _ => None,
+        }
+    }
+}
+
+/// Что симуляция просит сделать владельца окна и GPU-ресурсов
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// Действие, которое касается рендера, а не симуляции
+    Action(Action),
+    /// Воксель изменён, освещение уже пересчитано
+    VoxelChanged([usize; 3]),
+    /// Захватить или отпустить мышь
+    GrabMouse(bool),
+    /// Закладки или пути камеры изменились, их стоит сохранить
+    CameraPresetsChanged,
+}
+
+/// Всё, что меняется от ввода и времени: камера, клавиши и сетка вокселей.
+/// Не знает ни об окне, ни о GPU.
+pub struct Simulation {
+    pub camera: Camera,
+    pub controls: Controls,
+    pub voxel_grid: VoxelGrid,
+    pub light_volume: LightVolume,
+    pub mouse_grabbed: bool,
+    pub presets: CameraPresets,
+    pub bookmark: usize, // Следующая закладка для NextBookmark
+    pub playback: Option<PathPlayback>,
+    pub tick: u64, // Сколько шагов FIXED_DT прошло
+}
+
+impl Simulation {
+    pub fn new(voxel_grid: VoxelGrid, config: &Config) -> Self {
+        let mut camera = Camera::new(1.0);
+        camera.sensitivity = config.mouse.sensitivity;
+        camera.invert_y = config.mouse.invert_y;
+        Self {
+            camera,
+            controls: Controls::new(config.bindings.clone()),
+            light_volume: LightVolume::new(&voxel_grid),
+            voxel_grid,
+            mouse_grabbed: false,
+            presets: CameraPresets::default(),
+            bookmark: 0,
+            playback: None,
+            tick: 0,
+        }
+    }
+
+    /// Тестовая сцена: её же воспроизводят записи ввода
+    pub fn with_test_pattern(config: &Config) -> Self {
+        let mut voxel_grid = VoxelGrid::new(16);
+        voxel_grid.fill_with_test_pattern();
+        Self::new(voxel_grid, config)
+    }
+
+    /// Один шаг симуляции длиной FIXED_DT
+    pub fn step(&mut self) {
+        match &mut self.playback {
+            // Во время пролёта камерой управляет путь
+            Some(playback) => {
+                if !playback.advance(&mut self.camera, FIXED_DT) {
+                    tracing::info!("Camera path `{}` finished", playback.path.name);
+                    self.playback = None;
+                }
+            }
+            None => {
+                self.camera.process_keyboard(&self.controls, FIXED_DT);
+                self.camera.update_orbit(FIXED_DT);
+            }
+        }
+        self.tick += 1;
+    }
+
+    /// Применяет событие ввода
+    pub fn apply(&mut self, event: &InputEvent) -> Option<Effect> {
+        match *event {
+            InputEvent::Key { key, pressed: true, repeat } => {
+                self.controls.press(key);
+                if repeat {
+                    return None;
+                }
+                self.controls.triggered(key).and_then(|action| self.run_action(action))
+            }
+            InputEvent::Key { key, pressed: false, .. } => {
+                self.controls.release(key);
+                None
+            }
+            InputEvent::Button { button, pressed } => self.press_button(button, pressed),
+            InputEvent::Wheel { lines } => {
+                self.camera.process_scroll(lines);
+                None
+            }
+            InputEvent::MouseMotion { dx, dy } => {
+                // В полёте взгляд крутится только при захваченной мыши, в орбите – при перетаскивании
+                if self.mouse_grabbed || self.camera.mode == CameraMode::Orbit {
+                    self.camera.process_mouse(dx, dy);
+                }
+                None
+            }
+            InputEvent::FocusLost => {
+                // Отпускания клавиш мы уже не увидим
+                self.controls.release_all();
+                self.grab_mouse(false)
+            }
+        }
+    }
+
+    fn grab_mouse(&mut self, grab: bool) -> Option<Effect> {
+        if grab == self.mouse_grabbed {
+            return None;
+        }
+        self.mouse_grabbed = grab;
+        Some(Effect::GrabMouse(grab))
+    }
+
+    /// Действия с камерой выполняет сама, остальные отдаёт владельцу
+    fn run_action(&mut self, action: Action) -> Option<Effect> {
+        match action {
+            Action::Reset => self.camera.reset(),
+            Action::ReleaseMouse => return self.grab_mouse(false),
+            // Свободный полёт / орбита вокруг точки под прицелом (в орбите нужен курсор)
+            Action::ToggleCameraMode => {
+                let focus = self.focus_distance();
+                self.camera.toggle_mode(focus);
+                return self.grab_mouse(false);
+            }
+            Action::FrameGrid => self.camera.frame_bounds(&self.voxel_grid.bounds()),
+            // Перспектива / ортография / изометрия, масштаб вокруг точки под прицелом
+            Action::CycleProjection => {
+                let focus = self.focus_distance();
+                self.camera.cycle_projection(focus);
+            }
+            Action::ViewTop | Action::ViewFront | Action::ViewSide => {
+                let view = match action {
+                    Action::ViewTop => AxisView::Top,
+                    Action::ViewFront => AxisView::Front,
+                    _ => AxisView::Side,
+                };
+                let focus = self.focus_distance();
+                self.camera.set_axis_view(view, focus);
+            }
+            Action::AddBookmark => {
+                let name = format!("bookmark {}", self.presets.bookmarks.len() + 1);
+                self.presets.bookmarks.push(Bookmark { name, pose: Pose::capture(&self.camera) });
+                return Some(Effect::CameraPresetsChanged);
+            }
+            Action::NextBookmark => {
+                let bookmarks = &self.presets.bookmarks;
+                if !bookmarks.is_empty() {
+                    let bookmark = &bookmarks[self.bookmark % bookmarks.len()];
+                    bookmark.pose.apply(&mut self.camera);
+                    self.bookmark = (self.bookmark + 1) % bookmarks.len();
+                }
+            }
+            Action::AddKeyframe => {
+                self.add_keyframe();
+                return Some(Effect::CameraPresetsChanged);
+            }
+            Action::ClearPath => {
+                self.presets.paths.retain(|p| p.name != RECORDED_PATH);
+                return Some(Effect::CameraPresetsChanged);
+            }
+            // Запуск первого пути или остановка текущего
+            Action::PlayPath => {
+                self.playback = match self.playback {
+                    Some(_) => None,
+                    None => self.presets.paths.first().cloned().map(PathPlayback::new),
+                };
+            }
+            _ => return Some(Effect::Action(action)),
+        }
+        None
+    }
+
+    /// Добавляет текущую позу в конец записываемого пути
+    fn add_keyframe(&mut self) {
+        let pose = Pose::capture(&self.camera);
+        let paths = &mut self.presets.paths;
+        let index = match paths.iter().position(|p| p.name == RECORDED_PATH) {
+            Some(index) => index,
+            None => {
+                paths.push(CameraPath { name: RECORDED_PATH.to_string(), keyframes: Vec::new() });
+                paths.len() - 1
+            }
+        };
+        let path = &mut paths[index];
+        let time = path.keyframes.last().map_or(0.0, |k| k.time + KEYFRAME_INTERVAL);
+        path.keyframes.push(Keyframe { time, pose });
+    }
+
+    fn press_button(&mut self, button: Button, pressed: bool) -> Option<Effect> {
+        let drag = match button {
+            Button::Left => Some(OrbitDrag::Rotate),
+            Button::Middle => Some(OrbitDrag::Pan),
+            Button::Right => None,
+        };
+        // В орбите левая и средняя кнопки управляют камерой, остальные редактируют как обычно
+        if self.camera.mode == CameraMode::Orbit && drag.is_some() {
+            if pressed {
+                self.camera.orbit.drag = drag;
+            } else if self.camera.orbit.drag == drag {
+                self.camera.orbit.drag = None;
+            }
+            None
+        } else if pressed && !self.mouse_grabbed && button == Button::Left {
+            // Первый щелчок в полёте только захватывает мышь
+            self.grab_mouse(true)
+        } else if pressed {
+            self.edit_voxel(button)
+        } else {
+            None
+        }
+    }
+
+    /// Расстояние до вокселя под прицелом, иначе до центра сетки вдоль взгляда
+    fn focus_distance(&self) -> f32 {
+        let (origin, direction) = self.camera.pick_ray();
+        let voxel_size = self.voxel_grid.voxel_size();
+        let grid_origin = self.voxel_grid.origin();
+        match raycast(&self.voxel_grid, origin, direction, self.camera.far) {
+            Some(hit) => {
+                let center = Point3::from(std::array::from_fn::<f32, 3, _>(|i| {
+                    grid_origin[i] + (hit.cell[i] as f32 + 0.5) * voxel_size
+                }));
+                (center - self.camera.position).norm()
+            }
+            // В ортографии луч начинается на ближней плоскости, расстояние считаем от камеры
+            None => (self.voxel_grid.bounds().center() - self.camera.position).dot(&direction),
+        }
+    }
+
+    /// ЛКМ – удалить воксель под прицелом, ПКМ – поставить перед ним цветную лампу
+    fn edit_voxel(&mut self, button: Button) -> Option<Effect> {
+        let (origin, direction) = self.camera.pick_ray();
+        let hit = raycast(&self.voxel_grid, origin, direction, self.camera.far)?;
+
+        let (cell, voxel) = match button {
+            Button::Left => (hit.cell, Voxel::empty()),
+            Button::Right => {
+                let [x, y, z] = hit.previous?;
+                let (r, g, b) = LAMP_COLORS[(x + y + z) % LAMP_COLORS.len()];
+                ([x, y, z], Voxel::new(VOXEL_EMISSIVE, r, g, b, 255))
+            }
+            Button::Middle => return None,
+        };
+        self.set_voxel(cell, voxel);
+        Some(Effect::VoxelChanged(cell))
+    }
+
+    /// Меняет воксель и инкрементально пересчитывает освещение
+    pub fn set_voxel(&mut self, [x, y, z]: [usize; 3], voxel: Voxel) {
+        self.voxel_grid.set(x, y, z, voxel);
+        self.light_volume.update(&self.voxel_grid, x, y, z);
+    }
+}